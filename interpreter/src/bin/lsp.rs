@@ -0,0 +1,6 @@
+use std::process::exit;
+
+// Note: a language server speaking LSP over stdin and stdout, see lox::lsp
+fn main() {
+    exit(lox::lsp::run());
+}
@@ -1,18 +1,24 @@
 use super::environment::Environment;
 use super::error::{Error, Result};
 use super::interpreter::Interpreter;
+use super::lox_class::LoxClass;
 use super::lox_instance::LoxInstance;
 use super::stmt::Stmt;
 use super::token::{Literal, Token};
 use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
-use std::time::SystemTime;
 
 use super::object::Object;
 
 pub trait LoxCallable {
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object>;
+    // Note: paren is the closing parenthesis of the call, used to report errors
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        paren: &Token,
+        arguments: Vec<Object>,
+    ) -> Result<Object>;
     fn arity(&self) -> usize;
 }
 
@@ -20,8 +26,8 @@ pub trait LoxCallable {
 pub struct LoxFunction {
     // Note: declaration
     name: Token,
-    params: Vec<Token>,
-    body: Vec<Stmt>,
+    params: Rc<Vec<Token>>,
+    body: Rc<Vec<Stmt>>,
     closure: Rc<RefCell<Environment>>,
     is_initializer: bool,
 }
@@ -29,8 +35,8 @@ pub struct LoxFunction {
 impl LoxFunction {
     pub fn new(
         name: Token,
-        params: Vec<Token>,
-        body: Vec<Stmt>,
+        params: Rc<Vec<Token>>,
+        body: Rc<Vec<Stmt>>,
         env: Rc<RefCell<Environment>>,
         is_initializer: bool,
     ) -> LoxFunction {
@@ -43,16 +49,29 @@ impl LoxFunction {
         }
     }
 
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
     pub fn bind(&self, instance: LoxInstance) -> LoxFunction {
+        self.bind_this(Object::Instance(instance))
+    }
+
+    // Note: static methods see their class as `this`
+    pub fn bind_class(&self, class: Rc<LoxClass>) -> LoxFunction {
+        self.bind_this(Object::Class(class))
+    }
+
+    fn bind_this(&self, this: Object) -> LoxFunction {
         let environement = Environment::new(
             Some(Rc::clone(&self.closure)),
             self.closure.borrow().is_repl,
         );
-        environement.define("this".to_string(), &Object::Instance(instance));
+        environement.define("this".to_string(), &this);
         LoxFunction::new(
             self.name.clone(),
-            self.params.clone(),
-            self.body.clone(),
+            Rc::clone(&self.params),
+            Rc::clone(&self.body),
             Rc::new(RefCell::new(environement)),
             self.is_initializer,
         )
@@ -60,7 +79,12 @@ impl LoxFunction {
 }
 
 impl LoxCallable for LoxFunction {
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object> {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _paren: &Token,
+        arguments: Vec<Object>,
+    ) -> Result<Object> {
         let environement = Environment::new(
             Some(Rc::clone(&self.closure)),
             self.closure.borrow().is_repl,
@@ -68,7 +92,25 @@ impl LoxCallable for LoxFunction {
         for (param, arg) in self.params.iter().zip(arguments.iter()) {
             environement.define(param.lexeme.clone(), arg)
         }
-        match interpreter.execute_block(&self.body, environement) {
+        // Note: functions of the prelude have line 0 and run unseen by the hook
+        let hook = match self.name.line {
+            0 => None,
+            _ => interpreter.hook.clone(),
+        };
+        if let Some(hook) = &hook {
+            interpreter.call_stack.push(self.name.lexeme.clone());
+            if let Ok(mut hook) = hook.try_borrow_mut() {
+                hook.enter_function(&self.name);
+            }
+        }
+        let result = interpreter.execute_block(&self.body, environement);
+        if let Some(hook) = &hook {
+            interpreter.call_stack.pop();
+            if let Ok(mut hook) = hook.try_borrow_mut() {
+                hook.exit_function();
+            }
+        }
+        match result {
             Ok(_) => {
                 if self.is_initializer {
                     return self.closure.borrow().get_at(0, "this".to_string());
@@ -90,24 +132,55 @@ impl fmt::Display for LoxFunction {
     }
 }
 
+pub type NativeFn = fn(&mut Interpreter, &Token, Vec<Object>) -> Result<Object>;
+
 #[derive(Debug, Clone)]
-pub struct Clock {}
+pub struct NativeFunction {
+    name: &'static str,
+    arity: usize,
+    function: NativeFn,
+    // Note: a bound method receives its receiver as the first argument
+    receiver: Option<Box<Object>>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &'static str, arity: usize, function: NativeFn) -> NativeFunction {
+        NativeFunction {
+            name,
+            arity,
+            function,
+            receiver: None,
+        }
+    }
+
+    pub fn bind(&self, receiver: Object) -> NativeFunction {
+        NativeFunction {
+            receiver: Some(Box::new(receiver)),
+            ..self.clone()
+        }
+    }
+}
 
-impl LoxCallable for Clock {
-    fn call(&self, _interpreter: &mut Interpreter, _arguments: Vec<Object>) -> Result<Object> {
-        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(n) => Ok(Object::Literal(Literal::Isize(n.as_millis() as isize))),
-            Err(_) => panic!("SystemTime before UNIX EPOCH!"),
+impl LoxCallable for NativeFunction {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        paren: &Token,
+        mut arguments: Vec<Object>,
+    ) -> Result<Object> {
+        if let Some(receiver) = &self.receiver {
+            arguments.insert(0, *receiver.clone());
         }
+        (self.function)(interpreter, paren, arguments)
     }
     fn arity(&self) -> usize {
-        0
+        self.arity
     }
 }
 
-impl fmt::Display for Clock {
+impl fmt::Display for NativeFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<native fn>")
+        write!(f, "<native fn {}>", self.name)
     }
 }
 
@@ -0,0 +1,213 @@
+use super::error::{Error, Result};
+use super::interpreter::{Hook, Interpreter};
+use super::object::Object;
+use super::stmt::Stmt;
+use super::token::Literal;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::rc::Rc;
+
+pub mod console;
+pub mod dap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    Continue,
+    StepIn,
+    StepOver,
+    StepOut,
+    Quit,
+}
+
+// Note: frames are counted with the script itself as 1
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Continue,
+    In,
+    Over(usize),
+    Out(usize),
+}
+
+// The user interface of the debugger, the command line or an editor speaking DAP.
+pub trait Frontend: fmt::Debug {
+    // Note: called once per stop, returns how the program should go on
+    fn paused(&mut self, session: &mut Session, reason: Reason) -> Resume;
+    // Note: what the program printed since the last statement, when the output is captured
+    fn output(&mut self, _text: &str) {}
+}
+
+// Pauses the program on breakpoints and after steps. Breakpoints are line numbers, a line
+// with several statements stops only at the first of them.
+#[derive(Debug)]
+pub struct Debugger {
+    pub breakpoints: BTreeSet<usize>,
+    step: Step,
+    entry: bool,
+    // Note: the current line of every frame, the script's own frame first
+    lines: Vec<usize>,
+    // Note: depth and line of the statement run last
+    previous: Option<(usize, usize)>,
+    frontend: Box<dyn Frontend>,
+}
+
+impl Debugger {
+    pub fn new(frontend: Box<dyn Frontend>, stop_on_entry: bool) -> Debugger {
+        Debugger {
+            breakpoints: BTreeSet::new(),
+            step: if stop_on_entry {
+                Step::In
+            } else {
+                Step::Continue
+            },
+            entry: stop_on_entry,
+            lines: Vec::new(),
+            previous: None,
+            frontend,
+        }
+    }
+
+    fn reason(&self, depth: usize, line: usize) -> Option<Reason> {
+        let stepped = match self.step {
+            Step::Continue => false,
+            Step::In => true,
+            Step::Over(from) => depth <= from,
+            Step::Out(from) => depth < from,
+        };
+        if stepped {
+            return Some(if self.entry {
+                Reason::Entry
+            } else {
+                Reason::Step
+            });
+        }
+        if self.breakpoints.contains(&line) && self.previous != Some((depth, line)) {
+            return Some(Reason::Breakpoint);
+        }
+        None
+    }
+}
+
+impl Hook for Debugger {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        _statement: &Stmt,
+        line: usize,
+    ) -> Result<()> {
+        let depth = interpreter.call_stack.len() + 1;
+        self.lines.resize(depth, line);
+        self.lines[depth - 1] = line;
+        if let Some(output) = &interpreter.output {
+            let text = std::mem::take(&mut *output.borrow_mut());
+            if !text.is_empty() {
+                self.frontend.output(&text);
+            }
+        }
+
+        let reason = self.reason(depth, line);
+        self.previous = Some((depth, line));
+        let reason = match reason {
+            Some(reason) => reason,
+            None => return Ok(()),
+        };
+        self.entry = false;
+        let resume = {
+            let mut session = Session {
+                interpreter,
+                lines: &self.lines,
+                breakpoints: &mut self.breakpoints,
+            };
+            self.frontend.paused(&mut session, reason)
+        };
+        self.step = match resume {
+            Resume::Continue => Step::Continue,
+            Resume::StepIn => Step::In,
+            Resume::StepOver => Step::Over(depth),
+            Resume::StepOut => Step::Out(depth),
+            Resume::Quit => return Err(Error::Exit(0)),
+        };
+        Ok(())
+    }
+}
+
+// What a frontend can do with the paused program.
+pub struct Session<'a> {
+    interpreter: &'a mut Interpreter,
+    lines: &'a [usize],
+    pub breakpoints: &'a mut BTreeSet<usize>,
+}
+
+impl<'a> Session<'a> {
+    pub fn line(&self) -> usize {
+        *self.lines.last().unwrap()
+    }
+
+    // Returns the function name and current line of every frame, innermost first.
+    pub fn backtrace(&self) -> Vec<(String, usize)> {
+        let names = std::iter::once(String::from("<script>"))
+            .chain(self.interpreter.call_stack.iter().cloned());
+        let mut frames: Vec<(String, usize)> = names.zip(self.lines.iter().cloned()).collect();
+        frames.reverse();
+        frames
+    }
+
+    // Returns the names and values of the environment chain of the innermost frame, innermost
+    // environment first and the globals last. Natives are left out of the globals.
+    pub fn scopes(&mut self) -> Vec<Vec<(String, String)>> {
+        let mut objects = vec![];
+        let mut environment = Some(self.interpreter.environment());
+        while let Some(current) = environment {
+            let current = current.borrow();
+            let is_globals = current.enclosing.is_none();
+            let mut values: Vec<(String, Object)> = current
+                .values
+                .borrow()
+                .iter()
+                .filter(|(_, value)| !(is_globals && matches!(value, Object::Native(_))))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            values.sort_by(|a, b| a.0.cmp(&b.0));
+            objects.push(values);
+            environment = current.enclosing.as_ref().map(Rc::clone);
+        }
+        objects
+            .into_iter()
+            .map(|values| {
+                values
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let text = self.describe(&value);
+                        (name, text)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn evaluate(&mut self, source: &str) -> std::result::Result<String, String> {
+        match self.interpreter.evaluate_in_frame(source) {
+            Ok(value) => Ok(self.describe(&value)),
+            Err(Error::Throw(value, _)) => Err(format!("Uncaught {}", self.describe(&value))),
+            Err(Error::RuntimeError(_, message)) => Err(message),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Note: strings are quoted so they can be told apart from other values
+    fn describe(&mut self, value: &Object) -> String {
+        match self.interpreter.stringify(value) {
+            Ok(text) => match value {
+                Object::Literal(Literal::String(_)) => format!("\"{}\"", text),
+                _ => text,
+            },
+            Err(e) => format!("<{}>", e),
+        }
+    }
+}
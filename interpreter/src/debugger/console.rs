@@ -0,0 +1,141 @@
+use super::{Frontend, Reason, Resume, Session};
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  c, continue        run until the next breakpoint
+  s, step            run until the next statement, entering calls
+  n, next            run until the next statement in this function or its callers
+  o, out             run until the current function returns
+  b, break LINE      set a breakpoint
+  d, delete LINE     remove a breakpoint
+  breakpoints        list the breakpoints
+  bt, backtrace      show the calls leading here
+  e, env             show the variables of every enclosing environment
+  p, print EXPR      evaluate an expression here
+  l, list            show the source around the current line
+  q, quit            stop the program
+An empty line repeats the last command.";
+
+// Note: reads commands from stdin, so scripts being debugged should not read stdin themselves
+#[derive(Debug)]
+pub struct Console {
+    lines: Vec<String>,
+    last_command: String,
+}
+
+impl Console {
+    pub fn new(source: &str) -> Console {
+        Console {
+            lines: source.lines().map(String::from).collect(),
+            last_command: String::new(),
+        }
+    }
+
+    fn show_line(&self, line: usize, current: bool) {
+        if let Some(text) = self.lines.get(line.wrapping_sub(1)) {
+            let marker = if current { ">" } else { " " };
+            println!("{}{:>4} | {}", marker, line, text);
+        }
+    }
+
+    // Returns the command to resume with, or None when the debugger should keep reading.
+    fn command(&mut self, session: &mut Session, input: &str) -> Option<Resume> {
+        let input = input.trim();
+        let (command, argument) = match input.find(' ') {
+            Some(space) => (&input[..space], input[space + 1..].trim()),
+            None => (input, ""),
+        };
+        match command {
+            "c" | "continue" => return Some(Resume::Continue),
+            "s" | "step" => return Some(Resume::StepIn),
+            "n" | "next" => return Some(Resume::StepOver),
+            "o" | "out" | "finish" => return Some(Resume::StepOut),
+            "q" | "quit" => return Some(Resume::Quit),
+            "b" | "break" | "d" | "delete" => match argument.parse::<usize>() {
+                Ok(line) if command.starts_with('b') => {
+                    session.breakpoints.insert(line);
+                    println!("Breakpoint at line {}.", line);
+                }
+                Ok(line) => {
+                    if session.breakpoints.remove(&line) {
+                        println!("Removed the breakpoint at line {}.", line);
+                    } else {
+                        println!("There is no breakpoint at line {}.", line);
+                    }
+                }
+                Err(_) => println!("Expect a line number."),
+            },
+            "breakpoints" => {
+                if session.breakpoints.is_empty() {
+                    println!("No breakpoints.");
+                }
+                for line in session.breakpoints.iter() {
+                    println!("line {}", line);
+                }
+            }
+            "bt" | "backtrace" => {
+                for (index, (name, line)) in session.backtrace().iter().enumerate() {
+                    println!("#{} {} at line {}", index, name, line);
+                }
+            }
+            "e" | "env" => {
+                let scopes = session.scopes();
+                let count = scopes.len();
+                for (index, values) in scopes.iter().enumerate() {
+                    if index + 1 == count {
+                        println!("globals:");
+                    } else {
+                        println!("environment {}:", index);
+                    }
+                    for (name, value) in values {
+                        println!("  {} = {}", name, value);
+                    }
+                }
+            }
+            "p" | "print" => match session.evaluate(argument) {
+                Ok(value) => println!("{}", value),
+                Err(message) => println!("Error: {}", message),
+            },
+            "l" | "list" => {
+                let line = session.line();
+                for number in line.saturating_sub(3).max(1)..=line + 3 {
+                    self.show_line(number, number == line);
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            _ => println!("Unknown command '{}', try 'help'.", command),
+        }
+        None
+    }
+}
+
+impl Frontend for Console {
+    fn paused(&mut self, session: &mut Session, reason: Reason) -> Resume {
+        let line = session.line();
+        match reason {
+            Reason::Entry => println!("Paused at the start, line {}.", line),
+            Reason::Breakpoint => println!("Breakpoint at line {}.", line),
+            Reason::Step => {}
+        }
+        self.show_line(line, true);
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            io::stdout().flush().unwrap();
+            let mut input = String::new();
+            match stdin.lock().read_line(&mut input) {
+                Ok(0) | Err(_) => return Resume::Quit,
+                Ok(_) => {}
+            }
+            if input.trim().is_empty() {
+                input = self.last_command.clone();
+            } else {
+                self.last_command = input.clone();
+            }
+            if let Some(resume) = self.command(session, &input) {
+                return resume;
+            }
+        }
+    }
+}
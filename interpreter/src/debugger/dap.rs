@@ -0,0 +1,413 @@
+use super::{Debugger, Frontend, Reason, Resume, Session};
+use crate::environment::Environment;
+use crate::error::Error;
+use crate::interpreter::Interpreter;
+use crate::lexer::Lexer;
+use crate::lsp::read_message;
+use crate::native;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, BufReader, Stdin, Stdout, Write};
+use std::rc::Rc;
+
+const THREAD_ID: i64 = 1;
+
+// Note: the Debug Adapter Protocol frames its messages like LSP
+#[derive(Debug)]
+struct Connection {
+    input: BufReader<Stdin>,
+    output: Stdout,
+    seq: i64,
+}
+
+impl Connection {
+    fn new() -> Connection {
+        Connection {
+            input: BufReader::new(io::stdin()),
+            output: io::stdout(),
+            seq: 0,
+        }
+    }
+
+    // Note: None once the client is gone
+    fn read(&mut self) -> Option<Value> {
+        loop {
+            match read_message(&mut self.input) {
+                Ok(Some(message)) => return Some(message),
+                Ok(None) => return None,
+                Err(_) => continue,
+            }
+        }
+    }
+
+    fn send(&mut self, mut message: Value) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let body = message.to_string();
+        // Note: a client which went away is noticed by the next read
+        let _ = write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = self.output.flush();
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&mut self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn output(&mut self, category: &str, text: &str) {
+        self.event("output", json!({ "category": category, "output": text }));
+    }
+}
+
+#[derive(Debug)]
+struct Launch {
+    program: String,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+// Answers the requests of an editor while the program is paused.
+#[derive(Debug)]
+struct Dap {
+    connection: Rc<RefCell<Connection>>,
+    program: String,
+    // Note: the scopes of the last stop, variablesReference is the index plus 1
+    scopes: Vec<Vec<(String, String)>>,
+}
+
+impl Dap {
+    fn stack_trace(&self, session: &Session) -> Value {
+        let frames: Vec<Value> = session
+            .backtrace()
+            .into_iter()
+            .enumerate()
+            .map(|(id, (name, line))| {
+                json!({
+                    "id": id,
+                    "name": name,
+                    "line": line,
+                    "column": 1,
+                    "source": { "path": self.program },
+                })
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    // Note: only the innermost frame has its environments at hand
+    fn scopes(&mut self, session: &mut Session, frame: i64) -> Value {
+        if frame != 0 {
+            return json!({ "scopes": [] });
+        }
+        self.scopes = session.scopes();
+        let count = self.scopes.len();
+        let scopes: Vec<Value> = (0..count)
+            .map(|index| {
+                let name = if index + 1 == count {
+                    "Globals"
+                } else if index == 0 {
+                    "Locals"
+                } else {
+                    "Closure"
+                };
+                json!({
+                    "name": name,
+                    "variablesReference": index + 1,
+                    "expensive": index + 1 == count,
+                })
+            })
+            .collect();
+        json!({ "scopes": scopes })
+    }
+
+    fn variables(&self, reference: i64) -> Value {
+        let variables: Vec<Value> = self
+            .scopes
+            .get((reference - 1) as usize)
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|(name, value)| {
+                        json!({ "name": name, "value": value, "variablesReference": 0 })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        json!({ "variables": variables })
+    }
+}
+
+impl Frontend for Dap {
+    fn paused(&mut self, session: &mut Session, reason: Reason) -> Resume {
+        let reason = match reason {
+            Reason::Entry => "entry",
+            Reason::Breakpoint => "breakpoint",
+            Reason::Step => "step",
+        };
+        self.connection.borrow_mut().event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+        loop {
+            let request = match self.connection.borrow_mut().read() {
+                Some(request) => request,
+                None => return Resume::Quit,
+            };
+            let arguments = &request["arguments"];
+            let (body, resume) = match request["command"].as_str().unwrap_or("") {
+                "threads" => (threads(), None),
+                "stackTrace" => (self.stack_trace(session), None),
+                "scopes" => {
+                    let frame = arguments["frameId"].as_i64().unwrap_or(0);
+                    (self.scopes(session, frame), None)
+                }
+                "variables" => {
+                    let reference = arguments["variablesReference"].as_i64().unwrap_or(0);
+                    (self.variables(reference), None)
+                }
+                "evaluate" => {
+                    let expression = arguments["expression"].as_str().unwrap_or("");
+                    match session.evaluate(expression) {
+                        Ok(result) => (json!({ "result": result, "variablesReference": 0 }), None),
+                        Err(message) => {
+                            self.connection.borrow_mut().fail(&request, &message);
+                            continue;
+                        }
+                    }
+                }
+                "setBreakpoints" => (set_breakpoints(session.breakpoints, arguments), None),
+                "continue" => (
+                    json!({ "allThreadsContinued": true }),
+                    Some(Resume::Continue),
+                ),
+                "next" => (json!({}), Some(Resume::StepOver)),
+                "stepIn" => (json!({}), Some(Resume::StepIn)),
+                "stepOut" => (json!({}), Some(Resume::StepOut)),
+                "disconnect" | "terminate" => (json!({}), Some(Resume::Quit)),
+                "pause" | "setExceptionBreakpoints" | "configurationDone" => (json!({}), None),
+                _ => {
+                    self.connection
+                        .borrow_mut()
+                        .fail(&request, "Unsupported request.");
+                    continue;
+                }
+            };
+            self.connection.borrow_mut().respond(&request, body);
+            if let Some(resume) = resume {
+                return resume;
+            }
+        }
+    }
+
+    fn output(&mut self, text: &str) {
+        self.connection.borrow_mut().output("stdout", text);
+    }
+}
+
+fn threads() -> Value {
+    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })
+}
+
+// Note: a program is a single file, so the source of the request is not looked at
+fn set_breakpoints(breakpoints: &mut BTreeSet<usize>, arguments: &Value) -> Value {
+    breakpoints.clear();
+    let requested = arguments["breakpoints"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let mut verified = vec![];
+    for breakpoint in requested {
+        if let Some(line) = breakpoint["line"].as_u64() {
+            breakpoints.insert(line as usize);
+            verified.push(json!({ "verified": true, "line": line }));
+        }
+    }
+    json!({ "breakpoints": verified })
+}
+
+// Speaks DAP over stdin and stdout for one debugging session and returns the exit status.
+// The program and its arguments come from the `launch` request.
+pub fn run() -> i32 {
+    let connection = Rc::new(RefCell::new(Connection::new()));
+    let mut breakpoints = BTreeSet::new();
+    let mut launch = None;
+    loop {
+        let request = match connection.borrow_mut().read() {
+            Some(request) => request,
+            None => return 1,
+        };
+        let arguments = &request["arguments"];
+        let body = match request["command"].as_str().unwrap_or("") {
+            "initialize" => json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsEvaluateForHovers": true,
+            }),
+            "launch" => {
+                let program = match arguments["program"].as_str() {
+                    Some(program) => String::from(program),
+                    None => {
+                        connection
+                            .borrow_mut()
+                            .fail(&request, "Expect a program to launch.");
+                        continue;
+                    }
+                };
+                launch = Some(Launch {
+                    program,
+                    args: arguments["args"]
+                        .as_array()
+                        .map(|args| {
+                            args.iter()
+                                .filter_map(|arg| arg.as_str().map(String::from))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+                });
+                json!({})
+            }
+            "setBreakpoints" => set_breakpoints(&mut breakpoints, arguments),
+            "setExceptionBreakpoints" => json!({}),
+            "threads" => threads(),
+            "disconnect" => {
+                connection.borrow_mut().respond(&request, json!({}));
+                return 0;
+            }
+            "configurationDone" if launch.is_some() => {
+                connection.borrow_mut().respond(&request, json!({}));
+                break;
+            }
+            _ => {
+                connection
+                    .borrow_mut()
+                    .fail(&request, "Unsupported request.");
+                continue;
+            }
+        };
+        let initialize = request["command"] == "initialize";
+        connection.borrow_mut().respond(&request, body);
+        if initialize {
+            connection.borrow_mut().event("initialized", json!({}));
+        }
+    }
+
+    let launch = launch.unwrap();
+    let code = debug(&connection, launch, breakpoints);
+    {
+        let mut connection = connection.borrow_mut();
+        connection.event("exited", json!({ "exitCode": code }));
+        connection.event("terminated", json!({}));
+    }
+    loop {
+        let request = match connection.borrow_mut().read() {
+            Some(request) => request,
+            None => return 0,
+        };
+        match request["command"].as_str().unwrap_or("") {
+            "disconnect" => {
+                connection.borrow_mut().respond(&request, json!({}));
+                return 0;
+            }
+            "threads" => connection.borrow_mut().respond(&request, threads()),
+            _ => connection
+                .borrow_mut()
+                .fail(&request, "The program has finished."),
+        }
+    }
+}
+
+// Returns the exit status of the program.
+fn debug(
+    connection: &Rc<RefCell<Connection>>,
+    launch: Launch,
+    breakpoints: BTreeSet<usize>,
+) -> i32 {
+    let source = match fs::read_to_string(&launch.program) {
+        Ok(source) => source,
+        Err(e) => {
+            let message = format!("Cannot read {}: {}\n", launch.program, e);
+            connection.borrow_mut().output("stderr", &message);
+            return 66;
+        }
+    };
+    let mut interpreter = Interpreter::new(Environment::new(None, false));
+    native::process::define_args(&interpreter.globals.borrow(), launch.args);
+    let output = Rc::new(RefCell::new(String::new()));
+    interpreter.output = Some(Rc::clone(&output));
+
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize_all();
+    if let Some((line, message)) = lexer.errors.first() {
+        let message = format!("[line {}] Error: {}\n", line, message);
+        connection.borrow_mut().output("stderr", &message);
+        return 70;
+    }
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) => statements,
+        Err(e) => {
+            let message = format!("[line {}] Error: {}\n", e.1.line, e.0);
+            connection.borrow_mut().output("stderr", &message);
+            return 70;
+        }
+    };
+    if let Err(e) = Resolver::new(&mut interpreter).resolve_statements(&statements) {
+        connection
+            .borrow_mut()
+            .output("stderr", &format!("{}\n", e));
+        return 70;
+    }
+
+    let mut debugger = Debugger::new(
+        Box::new(Dap {
+            connection: Rc::clone(connection),
+            program: launch.program,
+            scopes: vec![],
+        }),
+        launch.stop_on_entry,
+    );
+    debugger.breakpoints = breakpoints;
+    interpreter.hook = Some(Rc::new(RefCell::new(debugger)));
+    let code = match interpreter.interpret(statements) {
+        Ok(()) => 0,
+        Err(Error::Exit(code)) => code,
+        Err(e) => {
+            connection
+                .borrow_mut()
+                .output("stderr", &format!("{}\n", e));
+            70
+        }
+    };
+    let text = std::mem::take(&mut *output.borrow_mut());
+    if !text.is_empty() {
+        connection.borrow_mut().output("stdout", &text);
+    }
+    code
+}
@@ -36,22 +36,37 @@ impl Environment {
         }
         Err(Error::RuntimeError(
             name.clone(),
-            format!("Undefined variableble '{}'.", &name.lexeme),
+            format!("Undefined variable '{}'.", &name.lexeme),
         ))
     }
 
     pub fn get_at(&self, distance: usize, name: String) -> Result<Object> {
-        match self.ancestor(distance).values.borrow().get(&name) {
-            Some(o) => Ok(o.clone()),
+        let found = if distance == 0 {
+            self.values.borrow().get(&name).cloned()
+        } else {
+            self.ancestor(distance)
+                .borrow()
+                .values
+                .borrow()
+                .get(&name)
+                .cloned()
+        };
+        match found {
+            Some(o) => Ok(o),
             None => unreachable!(),
         }
     }
 
     pub fn assign_at(&self, distance: usize, name: Token, value: Object) {
-        self.ancestor(distance)
-            .values
-            .borrow_mut()
-            .insert(name.lexeme, value);
+        if distance == 0 {
+            self.values.borrow_mut().insert(name.lexeme, value);
+        } else {
+            self.ancestor(distance)
+                .borrow()
+                .values
+                .borrow_mut()
+                .insert(name.lexeme, value);
+        }
     }
 
     pub fn assign(&self, name: &Token, value: &Object) -> Result<()> {
@@ -71,14 +86,21 @@ impl Environment {
         ))
     }
 
-    fn ancestor(&self, distance: usize) -> Environment {
-        let mut environment = self.clone();
-        for _i in 0..distance {
-            let enclosing = environment
-                .enclosing
-                .unwrap_or_else(|| panic!("No enclosing format at distance: {}", distance))
-                .borrow()
-                .clone();
+    // Note: walks the shared enclosing chain, so distance must be greater than 0
+    fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(
+            self.enclosing
+                .as_ref()
+                .unwrap_or_else(|| panic!("No enclosing format at distance: {}", distance)),
+        );
+        for _i in 1..distance {
+            let enclosing = Rc::clone(
+                environment
+                    .borrow()
+                    .enclosing
+                    .as_ref()
+                    .unwrap_or_else(|| panic!("No enclosing format at distance: {}", distance)),
+            );
             environment = enclosing
         }
         environment
@@ -2,17 +2,42 @@ use super::object::Object;
 use super::token::Token;
 use super::token_type::TokenType;
 use log::error;
+use std::fmt;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum Error {
     Return(Object),
+    // Note: value raised by `throw` and the line of the throw statement
+    Throw(Object, usize),
     ParseError(String),
     RuntimeError(Token, String),
     ResolveError(Token, String),
+    // Note: raised by `exit()`, carries the process exit status
+    Exit(i32),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Return(value) => write!(f, "Unexpected return of {}.", value),
+            Error::Throw(value, line) => write!(f, "[line {}] Uncaught {}", line, value),
+            Error::ParseError(message) => write!(f, "{}", message),
+            Error::RuntimeError(token, message) => {
+                write!(f, "[line {}] Error: {}", token.line, message)
+            }
+            Error::ResolveError(token, message) => write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                token.line, token.lexeme, message
+            ),
+            Error::Exit(code) => write!(f, "Exit with status {}.", code),
+        }
+    }
+}
+
 fn report(line: usize, place: &str, message: &str) {
     error!("[line {}] Error {}: {}", line, place, message);
 }
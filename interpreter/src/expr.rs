@@ -3,15 +3,21 @@ use super::token::Token;
 
 pub trait Visitor<T> {
     fn visit_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> T;
+    fn visit_conditional(&mut self, condition: &Expr, then_branch: &Expr, else_branch: &Expr) -> T;
     fn visit_grouping(&mut self, expression: &Expr) -> T;
     fn visit_literal(&mut self, expr: &token::Literal) -> T;
     fn visit_logical(&mut self, left: &Expr, operator: &Token, right: &Expr) -> T;
     fn visit_unary(&mut self, operator: &Token, right: &Expr) -> T;
     fn visit_variable(&mut self, name: &Token) -> T;
     fn visit_assign(&mut self, name: &Token, value: &Expr) -> T;
+    fn visit_compound_assign(&mut self, target: &Expr, operator: &Token, value: &Expr) -> T;
+    fn visit_increment(&mut self, target: &Expr, operator: &Token, postfix: bool) -> T;
     fn visit_call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> T;
     fn visit_get(&mut self, object: &Expr, name: &Token) -> T;
     fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) -> T;
+    fn visit_list(&mut self, elements: &[Expr]) -> T;
+    fn visit_index(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> T;
+    fn visit_index_set(&mut self, object: &Expr, bracket: &Token, index: &Expr, value: &Expr) -> T;
     fn visit_this(&mut self, keyword: &Token) -> T;
     fn visit_super(&mut self, keyword: &Token, method: &Token) -> T;
 }
@@ -31,6 +37,11 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Get {
         object: Box<Expr>,
         name: Token,
@@ -65,6 +76,32 @@ pub enum Expr {
         name: Token,
         value: Box<Expr>,
     },
+    // Note: target is Expr::Variable, Expr::Get or Expr::Index
+    CompoundAssign {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    // Note: `++` or `--`, target is the same as CompoundAssign
+    Increment {
+        target: Box<Expr>,
+        operator: Token,
+        postfix: bool,
+    },
+    List {
+        elements: Vec<Expr>,
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    IndexSet {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
     Call {
         callee: Box<Expr>,
         paren: Token,
@@ -72,6 +109,39 @@ pub enum Expr {
     },
 }
 
+impl Expr {
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Expr::Binary { left, operator, .. } | Expr::Logical { left, operator, .. } => {
+                left.line().or(Some(operator.line))
+            }
+            Expr::Unary { operator, .. } => Some(operator.line),
+            Expr::Conditional { condition, .. } => condition.line(),
+            Expr::Get { object, name } | Expr::Set { object, name, .. } => {
+                object.line().or(Some(name.line))
+            }
+            Expr::Grouping { expression } => expression.line(),
+            Expr::Literal { .. } => None,
+            Expr::Super { keyword, .. } | Expr::This { keyword } => Some(keyword.line),
+            Expr::Variable { name } | Expr::Assign { name, .. } => Some(name.line),
+            Expr::CompoundAssign {
+                target, operator, ..
+            }
+            | Expr::Increment {
+                target, operator, ..
+            } => target.line().or(Some(operator.line)),
+            Expr::List { elements } => elements.first().and_then(Expr::line),
+            Expr::Index {
+                object, bracket, ..
+            }
+            | Expr::IndexSet {
+                object, bracket, ..
+            } => object.line().or(Some(bracket.line)),
+            Expr::Call { callee, paren, .. } => callee.line().or(Some(paren.line)),
+        }
+    }
+}
+
 impl<T> Acceptor<T> for Expr {
     fn accept(&self, visitor: &mut dyn Visitor<T>) -> T {
         match self {
@@ -81,6 +151,11 @@ impl<T> Acceptor<T> for Expr {
                 right,
             } => visitor.visit_binary(left, operator, right),
             Expr::Unary { operator, right } => visitor.visit_unary(operator, right),
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_conditional(condition, then_branch, else_branch),
             Expr::Grouping { expression } => visitor.visit_grouping(expression),
             Expr::Literal { value } => visitor.visit_literal(value),
             Expr::Logical {
@@ -90,6 +165,28 @@ impl<T> Acceptor<T> for Expr {
             } => visitor.visit_logical(left, operator, right),
             Expr::Variable { name } => visitor.visit_variable(name),
             Expr::Assign { name, value } => visitor.visit_assign(name, value),
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => visitor.visit_compound_assign(target, operator, value),
+            Expr::Increment {
+                target,
+                operator,
+                postfix,
+            } => visitor.visit_increment(target, operator, *postfix),
+            Expr::List { elements } => visitor.visit_list(elements),
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => visitor.visit_index(object, bracket, index),
+            Expr::IndexSet {
+                object,
+                bracket,
+                index,
+                value,
+            } => visitor.visit_index_set(object, bracket, index, value),
             Expr::Call {
                 callee,
                 paren,
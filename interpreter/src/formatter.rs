@@ -0,0 +1,415 @@
+use super::error::{Error, Result};
+use super::lexer::Lexer;
+use super::parser::Parser;
+use super::token::Token;
+use super::token_type::TokenType;
+
+const INDENT: &str = "  ";
+const MAX_WIDTH: usize = 100;
+
+// Note: works on tokens rather than the AST because the parser drops comments and desugars `for`
+pub fn format(source: &str) -> Result<String> {
+    let mut lexer = Lexer::new(String::from(source)).preserving_comments();
+    let tokens = lexer.tokenize_all();
+    if !lexer.errors.is_empty() {
+        return Err(Error::ParseError(String::from(
+            "Cannot format a file with lexical errors.",
+        )));
+    }
+    let code: Vec<Token> = tokens
+        .iter()
+        .filter(|token| token.token_type != TokenType::Comment)
+        .cloned()
+        .collect();
+    if let Err(e) = Parser::new(code.clone()).parse() {
+        return Err(Error::ParseError(e.0));
+    }
+
+    let formatted = Formatter::new(&tokens).format();
+
+    // Note: the formatter must never change what the program means
+    let mut relexer = Lexer::new(formatted.clone());
+    let relexed = relexer.tokenize_all();
+    let same = relexed.len() == code.len()
+        && relexed
+            .iter()
+            .zip(code.iter())
+            .all(|(a, b)| a.token_type == b.token_type && a.lexeme == b.lexeme);
+    if !same {
+        return Err(Error::ParseError(String::from(
+            "Formatting would change the program, leaving it untouched.",
+        )));
+    }
+    Ok(formatted)
+}
+
+struct Piece {
+    text: String,
+    token_type: TokenType,
+    space_before: bool,
+}
+
+#[derive(Default)]
+struct Line {
+    indent: usize,
+    pieces: Vec<Piece>,
+    comment: Option<String>,
+}
+
+struct Formatter<'a> {
+    tokens: &'a [Token],
+    current: usize,
+    output: String,
+    line: Line,
+    indent: usize,
+    // Note: unclosed `(` and `[`, a `;` inside them belongs to a for clause
+    paren_depth: usize,
+    // Note: unmatched `?` of conditional expressions, to tell their `:` from annotations
+    conditional_depth: usize,
+    after_annotation: bool,
+    after_type_name: bool,
+    prev_prefix: bool,
+    prev: Option<TokenType>,
+    // Note: the last line the previous token spans in the source
+    prev_end_line: usize,
+    // Note: true right after `{`, where blank lines are dropped
+    block_start: bool,
+    // Note: set when a trailing comment forced a break in the middle of a statement
+    continuation: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(tokens: &'a [Token]) -> Formatter<'a> {
+        Formatter {
+            tokens,
+            current: 0,
+            output: String::new(),
+            line: Line::default(),
+            indent: 0,
+            paren_depth: 0,
+            conditional_depth: 0,
+            after_annotation: false,
+            after_type_name: false,
+            prev_prefix: false,
+            prev: None,
+            prev_end_line: 0,
+            block_start: true,
+            continuation: false,
+        }
+    }
+
+    fn format(mut self) -> String {
+        while self.current < self.tokens.len() {
+            let token = &self.tokens[self.current];
+            self.current += 1;
+            match token.token_type {
+                TokenType::EOF => break,
+                TokenType::Comment => self.comment(token),
+                _ => self.token(token),
+            }
+            self.prev_end_line = self.tokens[self.current - 1].line;
+        }
+        self.end_line();
+        self.output
+    }
+
+    fn comment(&mut self, token: &Token) {
+        let text = String::from(token.lexeme.trim_end());
+        if self.prev.is_some() && token.line == self.prev_end_line {
+            if self.line.pieces.is_empty() {
+                // Note: the line was already ended, e.g. after `;` or `{`
+                self.reopen_last_line(&text);
+                return;
+            }
+            self.line.comment = Some(text);
+            self.end_line();
+            self.continuation = !self.at_statement_boundary();
+            return;
+        }
+        if !self.line.pieces.is_empty() {
+            self.end_line();
+            self.continuation = !self.at_statement_boundary();
+        }
+        self.blank_line_before(token);
+        self.line.indent = self.indent + self.continuation as usize;
+        self.line.comment = Some(text);
+        self.end_line();
+        self.block_start = false;
+    }
+
+    fn at_statement_boundary(&self) -> bool {
+        self.paren_depth == 0
+            && matches!(
+                self.prev,
+                Some(TokenType::Semicolon)
+                    | Some(TokenType::LeftBrace)
+                    | Some(TokenType::RightBrace)
+            )
+    }
+
+    fn reopen_last_line(&mut self, comment: &str) {
+        if self.output.ends_with('\n') {
+            self.output.pop();
+        }
+        self.output.push(' ');
+        self.output.push_str(comment);
+        self.output.push('\n');
+    }
+
+    fn token(&mut self, token: &Token) {
+        let token_type = token.token_type;
+        if self.line.pieces.is_empty() {
+            self.blank_line_before(token);
+            self.line.indent = self.indent + self.continuation as usize;
+        }
+        self.block_start = false;
+
+        if token_type == TokenType::RightBrace {
+            self.end_line();
+            self.indent = self.indent.saturating_sub(1);
+            self.line.indent = self.indent;
+            self.continuation = false;
+        }
+
+        let prefix = self.is_prefix(token_type);
+        let nullable = token_type == TokenType::Question && self.after_type_name;
+        let space_before = !self.line.pieces.is_empty() && self.space_before(token_type);
+        self.line.pieces.push(Piece {
+            text: token.lexeme.clone(),
+            token_type,
+            space_before,
+        });
+
+        self.after_type_name = self.after_annotation;
+        self.after_annotation = false;
+        match token_type {
+            TokenType::LeftParen | TokenType::LeftBracket => self.paren_depth += 1,
+            TokenType::RightParen | TokenType::RightBracket => {
+                self.paren_depth = self.paren_depth.saturating_sub(1)
+            }
+            TokenType::Question if !nullable => self.conditional_depth += 1,
+            TokenType::Colon => {
+                if self.conditional_depth > 0 {
+                    self.conditional_depth -= 1;
+                } else {
+                    self.after_annotation = true;
+                }
+            }
+            _ => {}
+        }
+        self.prev_prefix = prefix;
+        self.prev = Some(token_type);
+
+        match token_type {
+            TokenType::Semicolon if self.paren_depth == 0 => {
+                self.conditional_depth = 0;
+                self.continuation = false;
+                self.end_line();
+            }
+            TokenType::LeftBrace => {
+                self.conditional_depth = 0;
+                self.continuation = false;
+                if self.peek_type() == Some(TokenType::RightBrace) {
+                    // Note: an empty block stays on one line as `{}`
+                    let close = &self.tokens[self.current];
+                    self.current += 1;
+                    self.line.pieces.push(Piece {
+                        text: close.lexeme.clone(),
+                        token_type: TokenType::RightBrace,
+                        space_before: false,
+                    });
+                    self.prev = Some(TokenType::RightBrace);
+                    self.after_block();
+                } else {
+                    self.end_line();
+                    self.indent += 1;
+                    self.block_start = true;
+                }
+            }
+            TokenType::RightBrace => self.after_block(),
+            _ => {}
+        }
+    }
+
+    // Note: `} else {`, `} catch` and `} finally` stay on the line of the closing brace
+    fn after_block(&mut self) {
+        match self.peek_type() {
+            Some(TokenType::Else) | Some(TokenType::Catch) | Some(TokenType::Finally) => {}
+            _ => self.end_line(),
+        }
+    }
+
+    fn peek_type(&self) -> Option<TokenType> {
+        self.tokens.get(self.current).map(|token| token.token_type)
+    }
+
+    fn blank_line_before(&mut self, token: &Token) {
+        let start_line = token.line - token.lexeme.matches('\n').count();
+        if !self.block_start
+            && token.token_type != TokenType::RightBrace
+            && start_line > self.prev_end_line + 1
+            && !self.output.is_empty()
+            && !self.output.ends_with("\n\n")
+        {
+            self.output.push('\n');
+        }
+    }
+
+    fn prev_is_operand(&self) -> bool {
+        match self.prev {
+            Some(TokenType::PlusPlus) | Some(TokenType::MinusMinus) => !self.prev_prefix,
+            Some(prev) => is_operand_end(prev),
+            None => false,
+        }
+    }
+
+    fn is_prefix(&self, token_type: TokenType) -> bool {
+        match token_type {
+            TokenType::Bang | TokenType::Tilde => true,
+            TokenType::Minus | TokenType::PlusPlus | TokenType::MinusMinus => {
+                !self.prev_is_operand()
+            }
+            _ => false,
+        }
+    }
+
+    fn space_before(&self, token_type: TokenType) -> bool {
+        match token_type {
+            TokenType::RightParen
+            | TokenType::RightBracket
+            | TokenType::Comma
+            | TokenType::Semicolon
+            | TokenType::Dot => return false,
+            _ => {}
+        }
+        match self.prev {
+            Some(TokenType::LeftParen) | Some(TokenType::LeftBracket) | Some(TokenType::Dot) => {
+                return false
+            }
+            _ => {}
+        }
+        if self.prev_prefix {
+            // Note: `- -x` must not turn into the `--` operator
+            return self.prev == Some(TokenType::Minus)
+                && matches!(token_type, TokenType::Minus | TokenType::MinusMinus);
+        }
+        match token_type {
+            TokenType::LeftParen | TokenType::LeftBracket => !self.prev_is_operand(),
+            TokenType::PlusPlus | TokenType::MinusMinus => !self.prev_is_operand(),
+            TokenType::Question => !self.after_type_name,
+            TokenType::Colon => self.conditional_depth > 0,
+            _ => true,
+        }
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if line.pieces.is_empty() && line.comment.is_none() {
+            return;
+        }
+        let mut rendered = if line.pieces.is_empty() {
+            vec![INDENT.repeat(line.indent)]
+        } else {
+            wrap(&line.pieces, line.indent)
+        };
+        if let Some(comment) = line.comment {
+            let last = rendered.last_mut().unwrap();
+            if !line.pieces.is_empty() {
+                last.push(' ');
+            }
+            last.push_str(&comment);
+        }
+        for text in rendered {
+            self.output.push_str(&text);
+            self.output.push('\n');
+        }
+    }
+}
+
+fn is_operand_end(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Identifier
+            | TokenType::PrivateIdentifier
+            | TokenType::Number
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::This
+            | TokenType::Super
+            | TokenType::RightParen
+            | TokenType::RightBracket
+    )
+}
+
+fn render(pieces: &[Piece], indent: usize) -> String {
+    let mut text = INDENT.repeat(indent);
+    for (i, piece) in pieces.iter().enumerate() {
+        if i > 0 && piece.space_before {
+            text.push(' ');
+        }
+        text.push_str(&piece.text);
+    }
+    text
+}
+
+// Note: a long line is split at its first bracketed list, one element per line
+fn wrap(pieces: &[Piece], indent: usize) -> Vec<String> {
+    let text = render(pieces, indent);
+    if text.len() <= MAX_WIDTH {
+        return vec![text];
+    }
+    let mut depth = 0;
+    let mut open = None;
+    for (i, piece) in pieces.iter().enumerate() {
+        match piece.token_type {
+            TokenType::LeftParen | TokenType::LeftBracket => {
+                if depth == 0 {
+                    open = Some(i);
+                }
+                depth += 1;
+            }
+            TokenType::RightParen | TokenType::RightBracket => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(start) = open {
+                        if let Some(lines) = wrap_group(pieces, indent, start, i) {
+                            return lines;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    vec![text]
+}
+
+fn wrap_group(pieces: &[Piece], indent: usize, open: usize, close: usize) -> Option<Vec<String>> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut start = open + 1;
+    for (i, piece) in pieces.iter().enumerate().take(close).skip(open + 1) {
+        match piece.token_type {
+            TokenType::LeftParen | TokenType::LeftBracket => depth += 1,
+            TokenType::RightParen | TokenType::RightBracket => depth -= 1,
+            TokenType::Comma if depth == 0 => {
+                items.push(start..i + 1);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if items.is_empty() {
+        return None;
+    }
+    items.push(start..close);
+
+    let mut lines = vec![render(&pieces[..open + 1], indent)];
+    for item in items {
+        lines.extend(wrap(&pieces[item], indent + 1));
+    }
+    lines.push(render(&pieces[close..], indent));
+    Some(lines)
+}
@@ -1,36 +1,124 @@
-use super::callable::{Clock, LoxCallable};
+use super::callable::{LoxCallable, LoxFunction};
 use super::environment::Environment;
 use super::error::{Error, Result};
 use super::expr;
 use super::expr::{Acceptor as ExprAcceptor, Expr};
+use super::lexer::Lexer;
 use super::lox_class::LoxClass;
+use super::lox_instance::LoxInstance;
+use super::lox_trait::LoxTrait;
+use super::native;
+use super::native::random::Rng;
 use super::object::Object;
+use super::parser::Parser;
+use super::resolver::Resolver;
 use super::stmt;
 use super::stmt::{Acceptor as StmtAcceptor, Stmt};
 use super::token::{Literal, Token};
 use super::token_type::TokenType;
+use super::types::TypeAnnotation;
 use log::error;
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
 use std::rc::Rc;
+use std::time::Instant;
+
+// Note: Lox source evaluated into the globals of every new interpreter
+const PRELUDE: &str = include_str!("prelude.lox");
+
+// Note: an assignment target whose object and index are already evaluated,
+// so compound assignments evaluate them only once
+enum Place {
+    Variable(Token),
+    // Note: an instance or a class
+    Field(Object, Token),
+    Index(Object, Object, Token),
+}
+
+// Consulted by `execute` before every statement except blocks, the debugger pauses the
+// program from here. Returning an error stops the program.
+pub trait Hook: fmt::Debug {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        statement: &Stmt,
+        line: usize,
+    ) -> Result<()>;
+    // Note: called around every call of a Lox function, the profiler times them
+    fn enter_function(&mut self, _name: &Token) {}
+    fn exit_function(&mut self) {}
+}
 
 #[derive(Debug, Clone)]
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
     locals: HashMap<Expr, usize>,
+    // Note: `#name` tokens and the key they are stored under, `Base#name` for a field of Base
+    private_names: HashMap<Token, String>,
+    // Note: the prelude's `Error` class, used to surface runtime errors to `catch`
+    error_class: Option<Rc<LoxClass>>,
+    // Note: shared between clones so the sequence continues across REPL lines
+    pub rng: Rc<Rng>,
+    // Note: hosts embedding the interpreter can turn off the file and stdin natives
+    pub allow_io: bool,
+    // Note: origin of the monotonic `clock()`
+    pub started_at: Instant,
+    // Note: instances and lists being converted by `stringify`, to stop cycles
+    stringifying: Vec<usize>,
+    pub hook: Option<Rc<RefCell<dyn Hook>>>,
+    // Note: names of the functions being run, innermost last, only kept while there is a hook
+    pub call_stack: Vec<String>,
+    // Note: when set `print` appends here instead of writing to stdout
+    pub output: Option<Rc<RefCell<String>>>,
+    // Note: set while the debugger evaluates an expression the Resolver has not seen
+    dynamic_lookup: bool,
 }
 
 impl Interpreter {
     pub fn new(environment: Environment) -> Interpreter {
         let globals = Rc::new(RefCell::new(environment));
-        globals
-            .borrow_mut()
-            .define(String::from("clock"), &Object::Clock(Clock {}));
-        Interpreter {
+        native::define_natives(&globals.borrow());
+        let mut interpreter = Interpreter {
             globals: Rc::clone(&globals),
             environment: globals,
             locals: HashMap::new(),
+            private_names: HashMap::new(),
+            error_class: None,
+            rng: Rc::new(Rng::from_time()),
+            allow_io: true,
+            started_at: Instant::now(),
+            stringifying: Vec::new(),
+            hook: None,
+            call_stack: Vec::new(),
+            output: None,
+            dynamic_lookup: false,
+        };
+        interpreter.load_prelude();
+        interpreter
+    }
+
+    fn load_prelude(&mut self) {
+        let mut tokens = Lexer::new(String::from(PRELUDE)).tokenize_all();
+        // Note: line 0 marks prelude code, which hooks do not see
+        for token in tokens.iter_mut() {
+            token.line = 0;
+        }
+        let statements = Parser::new(tokens)
+            .parse()
+            .expect("prelude should be parsed.");
+        Resolver::new(self)
+            .resolve_statements(&statements)
+            .expect("prelude should be resolved.");
+        for statement in statements {
+            self.execute(&statement)
+                .expect("prelude should be executed.");
+        }
+        if let Some(Object::Class(class)) = self.globals.borrow().values.borrow().get("Error") {
+            self.error_class = Some(Rc::clone(class));
         }
     }
 
@@ -38,7 +126,12 @@ impl Interpreter {
         for statement in statements {
             match self.execute(&statement) {
                 Ok(_) => {}
-                Err(r) => error!("{:?}", r),
+                Err(Error::Exit(code)) => return Err(Error::Exit(code)),
+                Err(Error::Throw(value, line)) => match self.stringify(&value) {
+                    Ok(s) => error!("[line {}] Uncaught {}", line, s),
+                    Err(_) => error!("{}", Error::Throw(value, line)),
+                },
+                Err(r) => error!("{}", r),
             }
         }
         Ok(())
@@ -49,14 +142,73 @@ impl Interpreter {
         Ok(())
     }
 
+    pub fn resolve_private(&mut self, name: Token, key: String) {
+        self.private_names.insert(name, key);
+    }
+
+    // Note: a private field is keyed by its declaring class, so subclasses cannot overwrite it
+    fn property_name<'t>(&self, name: &'t Token) -> Cow<'t, Token> {
+        if name.token_type != TokenType::PrivateIdentifier {
+            return Cow::Borrowed(name);
+        }
+        match self.private_names.get(name) {
+            Some(key) => Cow::Owned(Token {
+                lexeme: key.clone(),
+                ..name.clone()
+            }),
+            None => Cow::Borrowed(name),
+        }
+    }
+
     fn evaluate(&mut self, expr: &Expr) -> Result<Object> {
         expr.accept(self)
     }
 
     fn execute(&mut self, stmt: &Stmt) -> Result<()> {
+        if let Some(hook) = self.hook.clone() {
+            if let (false, Some(line @ 1..=usize::MAX)) =
+                (matches!(stmt, Stmt::Block { .. }), stmt.line())
+            {
+                // Note: the hook is already borrowed while it evaluates something itself
+                if let Ok(mut hook) = hook.try_borrow_mut() {
+                    hook.before_statement(self, stmt, line)?;
+                }
+            }
+        }
         stmt.accept(self)
     }
 
+    pub fn environment(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.environment)
+    }
+
+    // Evaluates an expression in the current environment, so a paused program can be inspected.
+    pub fn evaluate_in_frame(&mut self, source: &str) -> Result<Object> {
+        let mut source = String::from(source.trim());
+        if !source.ends_with(';') {
+            source.push(';');
+        }
+        let mut tokens = Lexer::new(source).tokenize_all();
+        // Note: column 0 keeps these expressions apart from the resolved ones of the program
+        // and of the prelude
+        for token in tokens.iter_mut() {
+            token.line = 0;
+            token.column = 0;
+        }
+        let expression = match Parser::new(tokens).parse() {
+            Ok(mut statements) if statements.len() == 1 => match statements.pop() {
+                Some(Stmt::Expression { expression }) => expression,
+                _ => return Err(Error::ParseError(String::from("Expect an expression."))),
+            },
+            Ok(_) => return Err(Error::ParseError(String::from("Expect an expression."))),
+            Err(e) => return Err(Error::ParseError(e.0)),
+        };
+        self.dynamic_lookup = true;
+        let result = self.evaluate(&expression);
+        self.dynamic_lookup = false;
+        result
+    }
+
     fn is_truthy(&self, object: Object) -> bool {
         match object {
             Object::Literal(literal) => match literal {
@@ -64,17 +216,17 @@ impl Interpreter {
                 Literal::Bool(b) => b,
                 _ => true,
             },
-            // FIXME
-            _ => false,
+            _ => true,
         }
     }
 
     fn look_up_variable(&mut self, name: &Token, expr: &Expr) -> Result<Object> {
-        match self.locals.get(&expr) {
+        match self.locals.get(expr) {
             Some(distance) => self
                 .environment
                 .borrow()
-                .get_at(distance.clone(), name.lexeme.clone()),
+                .get_at(*distance, name.lexeme.clone()),
+            _ if self.dynamic_lookup => self.environment.borrow().get(name),
             _ => self.globals.borrow().get(name),
         }
     }
@@ -86,68 +238,123 @@ impl Interpreter {
                 (Literal::Bool(a), Literal::Bool(b)) => a == b,
                 (Literal::String(a), Literal::String(b)) => a == b,
                 (Literal::Isize(a), Literal::Isize(b)) => a == b,
+                (Literal::Isize(a), Literal::Float(b)) => a as f64 == b,
+                (Literal::Float(a), Literal::Isize(b)) => a == b as f64,
                 (Literal::Float(a), Literal::Float(b)) => a == b,
                 _ => false,
             },
+            (Object::List(a), Object::List(b)) => Rc::ptr_eq(&a, &b),
+            (Object::Class(a), Object::Class(b)) => Rc::ptr_eq(&a, &b),
+            (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(&a.fields, &b.fields),
             // FIXME
             _ => false,
         }
     }
 
-    pub fn execute_block(&mut self, statements: &[Stmt], environment: Environment) -> Result<()> {
-        let previous = self.environment.clone();
-        self.environment = Rc::new(RefCell::new(environment));
-        for statement in statements {
-            match self.execute(statement) {
-                Ok(_) => {}
-                Err(e) => {
-                    self.environment = previous;
-                    return Err(e);
+    // Converts a value to the text used by print and string conversions. Instances whose
+    // class defines toString() are shown by calling it, lists stringify their elements.
+    pub fn stringify(&mut self, value: &Object) -> Result<String> {
+        match value {
+            Object::Instance(instance) => {
+                let method = match instance.class().find_method("toString".to_string()) {
+                    Some(method) => method.clone(),
+                    None => return Ok(value.to_string()),
+                };
+                // Note: a toString() which converts its own instance gets the default form
+                let key = Rc::as_ptr(&instance.fields) as usize;
+                if self.stringifying.contains(&key) {
+                    return Ok(value.to_string());
                 }
-            };
+                self.stringifying.push(key);
+                let result = method
+                    .bind(instance.clone())
+                    .call(self, method.name(), Vec::new());
+                self.stringifying.pop();
+                match result? {
+                    Object::Literal(Literal::String(s)) => Ok(s),
+                    other => Err(Error::RuntimeError(
+                        method.name().clone(),
+                        format!("toString() must return a string but got {}.", other),
+                    )),
+                }
+            }
+            Object::List(list) => {
+                let key = Rc::as_ptr(list) as usize;
+                if self.stringifying.contains(&key) {
+                    return Ok(String::from("[...]"));
+                }
+                self.stringifying.push(key);
+                let elements = list.borrow().clone();
+                let parts: Result<Vec<String>> = elements
+                    .iter()
+                    .map(|element| match element {
+                        Object::Literal(Literal::String(s)) => Ok(format!("{:?}", s)),
+                        _ => self.stringify(element),
+                    })
+                    .collect();
+                self.stringifying.pop();
+                Ok(format!("[{}]", parts?.join(", ")))
+            }
+            _ => Ok(value.to_string()),
         }
-        self.environment = previous;
-        Ok(())
     }
-}
-
-impl expr::Visitor<Result<Object>> for Interpreter {
-    fn visit_grouping(&mut self, expr: &Expr) -> Result<Object> {
-        self.evaluate(expr)
-    }
-
-    fn visit_unary(&mut self, operator: &Token, right: &Expr) -> Result<Object> {
-        use super::token::Literal::{Bool, Float, Isize, None};
-        use super::token_type::TokenType::{Bang, Minus};
 
-        let right = self.evaluate(right)?;
-        match (operator.token_type, right) {
-            (Minus, Object::Literal(lit)) => match lit {
-                Isize(r) => Ok(Object::Literal(Isize(-r))),
-                Float(r) => Ok(Object::Literal(Float(-r))),
-                _ => Err(Error::RuntimeError(
-                    operator.clone(),
-                    String::from("Operand must be a number."),
-                )),
-            },
-            (Bang, Object::Literal(lit)) => {
-                Ok(Object::Literal(Bool(!self.is_truthy(Object::Literal(lit)))))
+    // Converts an error raised inside a try block into the value bound by its catch clause.
+    // Errors which are not exceptions, such as return, are given back to be propagated.
+    fn catchable_value(&self, error: Error) -> std::result::Result<Object, Error> {
+        match error {
+            Error::Throw(value, _) => Ok(value),
+            Error::RuntimeError(token, message) => {
+                let class = self
+                    .error_class
+                    .clone()
+                    .expect("prelude should define Error class.");
+                let instance = LoxInstance::new(class);
+                {
+                    let mut fields = instance.fields.borrow_mut();
+                    fields.insert(
+                        "message".to_string(),
+                        Object::Literal(Literal::String(message)),
+                    );
+                    fields.insert(
+                        "line".to_string(),
+                        Object::Literal(Literal::Isize(token.line as isize)),
+                    );
+                }
+                Ok(Object::Instance(instance))
             }
-            _ => Ok(Object::Literal(None)),
+            e => Err(e),
         }
     }
 
-    fn visit_variable(&mut self, name: &Token) -> Result<Object> {
-        let expr = Expr::Variable { name: name.clone() };
-        self.look_up_variable(name, &expr)
-    }
-
-    fn visit_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<Object> {
+    fn binary_operation(
+        &mut self,
+        left: Object,
+        operator: &Token,
+        right: Object,
+    ) -> Result<Object> {
         use super::token::Literal::{Bool, Float, Isize, None, String as LString};
-        let left = self.evaluate(left)?;
-        let right = self.evaluate(right)?;
+
+        if let Object::Instance(instance) = &left {
+            if let Some(result) =
+                self.call_operator_method(instance, operator, vec![right.clone()])?
+            {
+                return Ok(result);
+            }
+        }
 
         match operator.token_type {
+            // Note: anything that is not an instance is never an instance of a class
+            TokenType::Is => match (left, right) {
+                (Object::Instance(instance), Object::Class(class)) => Ok(Object::Literal(Bool(
+                    instance.class().inherits_from(&class),
+                ))),
+                (_, Object::Class(_)) => Ok(Object::Literal(Bool(false))),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Right operand of 'is' must be a class."),
+                )),
+            },
             TokenType::Greater => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
                     (Isize(l), Isize(r)) => Ok(Object::Literal(Bool(l > r))),
@@ -159,7 +366,10 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
             },
             TokenType::GreaterEqual => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
@@ -172,7 +382,10 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
             },
             TokenType::Less => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
@@ -185,7 +398,10 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
             },
             TokenType::LessEqual => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
@@ -198,11 +414,16 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
             },
             TokenType::Minus => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
-                    (Isize(l), Isize(r)) => Ok(Object::Literal(Isize(l - r))),
+                    (Isize(l), Isize(r)) => {
+                        Interpreter::checked_integer(operator, l.checked_sub(r))
+                    }
                     (Isize(l), Float(r)) => Ok(Object::Literal(Float((l as f64) - r))),
                     (Float(l), Isize(r)) => Ok(Object::Literal(Float(l - r as f64))),
                     (Float(l), Float(r)) => Ok(Object::Literal(Float(l - r))),
@@ -211,25 +432,47 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
             },
             TokenType::Plus => match (left, right) {
+                // Note: a string concatenates with the stringified form of any other operand
+                (Object::Literal(LString(l)), r) => Ok(Object::Literal(LString(format!(
+                    "{}{}",
+                    l,
+                    self.stringify(&r)?
+                )))),
+                (l, Object::Literal(LString(r))) => Ok(Object::Literal(LString(format!(
+                    "{}{}",
+                    self.stringify(&l)?,
+                    r
+                )))),
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
-                    (Isize(l), Isize(r)) => Ok(Object::Literal(Isize(l + r))),
+                    (Isize(l), Isize(r)) => {
+                        Interpreter::checked_integer(operator, l.checked_add(r))
+                    }
                     (Isize(l), Float(r)) => Ok(Object::Literal(Float((l as f64) + r))),
                     (Float(l), Isize(r)) => Ok(Object::Literal(Float(l + r as f64))),
                     (Float(l), Float(r)) => Ok(Object::Literal(Float(l + r))),
-                    (LString(l), LString(r)) => Ok(Object::Literal(LString(format!("{}{}", l, r)))),
                     _ => Err(Error::RuntimeError(
                         operator.clone(),
-                        String::from("Operands must be numbers."),
+                        String::from("Operands must be two numbers or include a string."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be two numbers or include a string."),
+                )),
             },
             TokenType::Slash => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
-                    (Isize(l), Isize(r)) => Ok(Object::Literal(Isize(l / r))),
+                    (Isize(_), Isize(0)) => Err(Error::RuntimeError(
+                        operator.clone(),
+                        String::from("Division by zero."),
+                    )),
+                    (Isize(l), Isize(r)) => Ok(Object::Literal(Float(l as f64 / r as f64))),
                     (Isize(l), Float(r)) => Ok(Object::Literal(Float((l as f64) / r))),
                     (Float(l), Isize(r)) => Ok(Object::Literal(Float(l / r as f64))),
                     (Float(l), Float(r)) => Ok(Object::Literal(Float(l / r))),
@@ -238,11 +481,45 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
+            },
+            TokenType::TildeSlash => match (left, right) {
+                (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
+                    (Isize(_), Isize(0)) => Err(Error::RuntimeError(
+                        operator.clone(),
+                        String::from("Division by zero."),
+                    )),
+                    (Isize(l), Isize(r)) => Interpreter::checked_integer(
+                        operator,
+                        l.checked_div(r).map(|q| {
+                            if l % r != 0 && (l < 0) != (r < 0) {
+                                q - 1
+                            } else {
+                                q
+                            }
+                        }),
+                    ),
+                    (Isize(l), Float(r)) => Ok(Object::Literal(Float(((l as f64) / r).floor()))),
+                    (Float(l), Isize(r)) => Ok(Object::Literal(Float((l / r as f64).floor()))),
+                    (Float(l), Float(r)) => Ok(Object::Literal(Float((l / r).floor()))),
+                    _ => Err(Error::RuntimeError(
+                        operator.clone(),
+                        String::from("Operands must be numbers."),
+                    )),
+                },
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
             },
             TokenType::Star => match (left, right) {
                 (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
-                    (Isize(l), Isize(r)) => Ok(Object::Literal(Isize(l * r))),
+                    (Isize(l), Isize(r)) => {
+                        Interpreter::checked_integer(operator, l.checked_mul(r))
+                    }
                     (Isize(l), Float(r)) => Ok(Object::Literal(Float((l as f64) * r))),
                     (Float(l), Isize(r)) => Ok(Object::Literal(Float(l * r as f64))),
                     (Float(l), Float(r)) => Ok(Object::Literal(Float(l * r))),
@@ -251,7 +528,86 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         String::from("Operands must be numbers."),
                     )),
                 },
-                _ => unreachable!(),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
+            },
+            TokenType::Percent => match (left, right) {
+                (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
+                    (Isize(_), Isize(0)) => Err(Error::RuntimeError(
+                        operator.clone(),
+                        String::from("Division by zero."),
+                    )),
+                    (Isize(l), Isize(r)) => {
+                        Interpreter::checked_integer(operator, l.checked_rem(r))
+                    }
+                    (Isize(l), Float(r)) => Ok(Object::Literal(Float((l as f64) % r))),
+                    (Float(l), Isize(r)) => Ok(Object::Literal(Float(l % r as f64))),
+                    (Float(l), Float(r)) => Ok(Object::Literal(Float(l % r))),
+                    _ => Err(Error::RuntimeError(
+                        operator.clone(),
+                        String::from("Operands must be numbers."),
+                    )),
+                },
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
+            },
+            TokenType::StarStar => match (left, right) {
+                (Object::Literal(oll), Object::Literal(olr)) => match (oll, olr) {
+                    (Isize(l), Isize(r)) if r >= 0 => Interpreter::checked_integer(
+                        operator,
+                        u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+                    ),
+                    (Isize(l), Isize(r)) => Ok(Object::Literal(Float((l as f64).powf(r as f64)))),
+                    (Isize(l), Float(r)) => Ok(Object::Literal(Float((l as f64).powf(r)))),
+                    (Float(l), Isize(r)) => Ok(Object::Literal(Float(l.powf(r as f64)))),
+                    (Float(l), Float(r)) => Ok(Object::Literal(Float(l.powf(r)))),
+                    _ => Err(Error::RuntimeError(
+                        operator.clone(),
+                        String::from("Operands must be numbers."),
+                    )),
+                },
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be numbers."),
+                )),
+            },
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => match (left, right) {
+                (Object::Literal(Isize(l)), Object::Literal(Isize(r))) => {
+                    let result = match operator.token_type {
+                        TokenType::Ampersand => l & r,
+                        TokenType::Pipe => l | r,
+                        TokenType::Caret => l ^ r,
+                        _ => {
+                            if r < 0 || r >= isize::BITS as isize {
+                                return Err(Error::RuntimeError(
+                                    operator.clone(),
+                                    format!(
+                                        "Shift amount must be between 0 and {}.",
+                                        isize::BITS - 1
+                                    ),
+                                ));
+                            }
+                            if operator.token_type == TokenType::LessLess {
+                                l << r
+                            } else {
+                                l >> r
+                            }
+                        }
+                    };
+                    Ok(Object::Literal(Isize(result)))
+                }
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operands must be integers."),
+                )),
             },
             TokenType::BangEqual => Ok(Object::Literal(Bool(!self.is_equal(left, right)))),
             TokenType::EqualEqual => Ok(Object::Literal(Bool(self.is_equal(left, right)))),
@@ -259,6 +615,417 @@ impl expr::Visitor<Result<Object>> for Interpreter {
         }
     }
 
+    // Calls the method a class defines to overload `operator`, such as `__add` for `+`.
+    // Returns None when the class does not overload it.
+    fn call_operator_method(
+        &mut self,
+        instance: &LoxInstance,
+        operator: &Token,
+        arguments: Vec<Object>,
+    ) -> Result<Option<Object>> {
+        let name = match LoxClass::operator_method_name(operator.token_type, arguments.len()) {
+            Some(name) => name,
+            None => return Ok(Option::None),
+        };
+        // Note: `!=` falls back to negating `__eq`
+        if operator.token_type == TokenType::BangEqual
+            && instance.class().find_method(name.to_string()).is_none()
+        {
+            let equal = Token {
+                token_type: TokenType::EqualEqual,
+                ..operator.clone()
+            };
+            return Ok(self
+                .call_operator_method(instance, &equal, arguments)?
+                .map(|result| Object::Literal(Literal::Bool(!self.is_truthy(result)))));
+        }
+        let method = match instance.class().find_method(name.to_string()) {
+            Some(method) => method.clone(),
+            None => return Ok(Option::None),
+        };
+        if method.arity() != arguments.len() {
+            return Err(Error::RuntimeError(
+                operator.clone(),
+                format!(
+                    "Operator method '{}' must take {} parameter(s) but takes {}.",
+                    name,
+                    arguments.len(),
+                    method.arity()
+                ),
+            ));
+        }
+        method
+            .bind(instance.clone())
+            .call(self, operator, arguments)
+            .map(Some)
+    }
+
+    pub fn get_property(&mut self, evaluated_object: Object, name: &Token) -> Result<Object> {
+        let name = &*self.property_name(name);
+        match evaluated_object {
+            // Note: a getter takes precedence over a field of the same name
+            Object::Instance(mut instance) => {
+                let getter = instance.class().find_getter(&name.lexeme).cloned();
+                match getter {
+                    Some(getter) => getter.bind(instance).call(self, name, Vec::new()),
+                    None => instance.get(name),
+                }
+            }
+            Object::Class(class) => {
+                if let Some(value) = class.find_field(&name.lexeme) {
+                    return Ok(value);
+                }
+                match class.find_class_method(name.lexeme.clone()) {
+                    Some(method) => Ok(Object::Func(method.bind_class(Rc::clone(&class)))),
+                    None => Err(Error::RuntimeError(
+                        name.clone(),
+                        format!("Undefined property '{}'.", name.lexeme),
+                    )),
+                }
+            }
+            Object::Literal(Literal::String(_)) => match native::string::method(&name.lexeme) {
+                Some(method) => Ok(Object::Native(method.bind(evaluated_object))),
+                None => Err(Error::RuntimeError(
+                    name.clone(),
+                    format!("Undefined string method '{}'.", name.lexeme),
+                )),
+            },
+            Object::List(_) => match native::list::method(&name.lexeme) {
+                Some(method) => Ok(Object::Native(method.bind(evaluated_object))),
+                None => Err(Error::RuntimeError(
+                    name.clone(),
+                    format!("Undefined list method '{}'.", name.lexeme),
+                )),
+            },
+            _ => Err(Error::RuntimeError(
+                name.clone(),
+                String::from("Only instances, classes, strings and lists have properties."),
+            )),
+        }
+    }
+
+    pub fn set_property(&mut self, object: Object, name: &Token, value: &Object) -> Result<()> {
+        let name = &*self.property_name(name);
+        match object {
+            Object::Instance(mut instance) => {
+                let setter = instance.class().find_setter(&name.lexeme).cloned();
+                match setter {
+                    Some(setter) => {
+                        setter
+                            .bind(instance)
+                            .call(self, name, vec![value.clone()])?;
+                    }
+                    None if instance.class().find_getter(&name.lexeme).is_some() => {
+                        return Err(Error::RuntimeError(
+                            name.clone(),
+                            format!("Cannot assign to read-only property '{}'.", name.lexeme),
+                        ));
+                    }
+                    None => instance.set(name, value)?,
+                }
+                Ok(())
+            }
+            Object::Class(class) => {
+                class
+                    .fields
+                    .borrow_mut()
+                    .insert(name.lexeme.clone(), value.clone());
+                Ok(())
+            }
+            _ => Err(Error::RuntimeError(
+                name.clone(),
+                String::from("Only instances and classes have fields."),
+            )),
+        }
+    }
+
+    // Creates the functions for the methods of a class declaration, closing over the
+    // current environment. Only instance methods named `init` are initializers.
+    fn method_table(
+        &self,
+        declarations: &[Stmt],
+        has_initializer: bool,
+    ) -> HashMap<String, LoxFunction> {
+        let mut methods = HashMap::new();
+        for method in declarations {
+            match method {
+                Stmt::Function {
+                    name: func_name,
+                    params,
+                    body,
+                    ..
+                } => {
+                    let function = LoxFunction::new(
+                        func_name.clone(),
+                        Rc::new(params.to_vec()),
+                        Rc::new(body.to_vec()),
+                        Rc::clone(&self.environment),
+                        has_initializer && func_name.lexeme == "init",
+                    );
+                    methods.insert(func_name.lexeme.clone(), function);
+                }
+                _ => unreachable!(),
+            }
+        }
+        methods
+    }
+
+    fn declares_method(methods: &[Stmt], name: &str) -> bool {
+        methods.iter().any(|method| match method {
+            Stmt::Function {
+                name: func_name, ..
+            } => func_name.lexeme == name,
+            _ => false,
+        })
+    }
+
+    fn assign_variable(&mut self, name: &Token, expr: &Expr, value: Object) -> Result<()> {
+        match self.locals.get(expr) {
+            Some(distance) => {
+                self.environment
+                    .borrow()
+                    .assign_at(*distance, name.clone(), value);
+                Ok(())
+            }
+            None if self.dynamic_lookup => self.environment.borrow().assign(name, &value),
+            None => self.globals.borrow_mut().assign(name, &value),
+        }
+    }
+
+    fn evaluate_place(&mut self, target: &Expr) -> Result<Place> {
+        match target {
+            Expr::Variable { name } => Ok(Place::Variable(name.clone())),
+            Expr::Get { object, name } => match self.evaluate(object)? {
+                field_object @ Object::Instance(_) | field_object @ Object::Class(_) => {
+                    Ok(Place::Field(field_object, name.clone()))
+                }
+                _ => Err(Error::RuntimeError(
+                    name.clone(),
+                    String::from("Only instances and classes have fields."),
+                )),
+            },
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let evaluated_object = self.evaluate(object)?;
+                let evaluated_index = self.evaluate(index)?;
+                Ok(Place::Index(
+                    evaluated_object,
+                    evaluated_index,
+                    bracket.clone(),
+                ))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_place(&mut self, target: &Expr, place: &Place) -> Result<Object> {
+        match place {
+            Place::Variable(name) => self.look_up_variable(name, target),
+            Place::Field(object, name) => self.get_property(object.clone(), name),
+            Place::Index(object, index, bracket) => self.index_get(object, index, bracket),
+        }
+    }
+
+    fn write_place(&mut self, target: &Expr, place: Place, value: Object) -> Result<()> {
+        match place {
+            Place::Variable(name) => self.assign_variable(&name, target, value),
+            Place::Field(object, name) => self.set_property(object, &name, &value),
+            Place::Index(object, index, bracket) => {
+                self.index_set(&object, &index, &bracket, value)
+            }
+        }
+    }
+
+    // Maps `+=`, `++` and friends to the binary operator they apply.
+    pub fn arithmetic_operator(operator: &Token) -> Token {
+        let token_type = match operator.token_type {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            TokenType::PercentEqual => TokenType::Percent,
+            _ => unreachable!(),
+        };
+        Token::new(
+            token_type,
+            operator.lexeme.clone(),
+            Literal::None,
+            operator.line,
+            operator.column,
+        )
+    }
+
+    fn checked_integer(operator: &Token, result: Option<isize>) -> Result<Object> {
+        match result {
+            Some(i) => Ok(Object::Literal(Literal::Isize(i))),
+            None => Err(Error::RuntimeError(
+                operator.clone(),
+                String::from("Integer overflow."),
+            )),
+        }
+    }
+
+    fn checked_index(index: &Object, len: usize, bracket: &Token) -> Result<usize> {
+        match index {
+            Object::Literal(Literal::Isize(i)) if *i >= 0 && (*i as usize) < len => Ok(*i as usize),
+            Object::Literal(Literal::Isize(i)) => Err(Error::RuntimeError(
+                bracket.clone(),
+                format!("Index {} is out of bounds for length {}.", i, len),
+            )),
+            _ => Err(Error::RuntimeError(
+                bracket.clone(),
+                String::from("Index must be an integer."),
+            )),
+        }
+    }
+
+    fn index_get(&mut self, object: &Object, index: &Object, bracket: &Token) -> Result<Object> {
+        match object {
+            Object::Instance(instance) => {
+                match self.call_operator_method(instance, bracket, vec![index.clone()])? {
+                    Some(result) => Ok(result),
+                    Option::None => Err(Error::RuntimeError(
+                        bracket.clone(),
+                        format!("{} does not define '__index'.", instance),
+                    )),
+                }
+            }
+            Object::List(list) => {
+                let list = list.borrow();
+                let i = Interpreter::checked_index(index, list.len(), bracket)?;
+                Ok(list[i].clone())
+            }
+            Object::Literal(Literal::String(s)) => {
+                let i = Interpreter::checked_index(index, s.chars().count(), bracket)?;
+                let c = s.chars().nth(i).expect("index should be checked.");
+                Ok(Object::Literal(Literal::String(c.to_string())))
+            }
+            _ => Err(Error::RuntimeError(
+                bracket.clone(),
+                String::from("Only lists, strings and instances can be indexed."),
+            )),
+        }
+    }
+
+    fn index_set(
+        &mut self,
+        object: &Object,
+        index: &Object,
+        bracket: &Token,
+        value: Object,
+    ) -> Result<()> {
+        match object {
+            Object::Instance(instance) => {
+                match self.call_operator_method(instance, bracket, vec![index.clone(), value])? {
+                    Some(_) => Ok(()),
+                    Option::None => Err(Error::RuntimeError(
+                        bracket.clone(),
+                        format!("{} does not define '__setindex'.", instance),
+                    )),
+                }
+            }
+            Object::List(list) => {
+                let mut list = list.borrow_mut();
+                let i = Interpreter::checked_index(index, list.len(), bracket)?;
+                list[i] = value;
+                Ok(())
+            }
+            _ => Err(Error::RuntimeError(
+                bracket.clone(),
+                String::from("Only lists and instances support index assignment."),
+            )),
+        }
+    }
+
+    pub fn execute_block(&mut self, statements: &[Stmt], environment: Environment) -> Result<()> {
+        let previous = self.environment.clone();
+        self.environment = Rc::new(RefCell::new(environment));
+        for statement in statements {
+            match self.execute(statement) {
+                Ok(_) => {}
+                Err(e) => {
+                    self.environment = previous;
+                    return Err(e);
+                }
+            };
+        }
+        self.environment = previous;
+        Ok(())
+    }
+}
+
+impl expr::Visitor<Result<Object>> for Interpreter {
+    fn visit_grouping(&mut self, expr: &Expr) -> Result<Object> {
+        self.evaluate(expr)
+    }
+
+    fn visit_unary(&mut self, operator: &Token, right: &Expr) -> Result<Object> {
+        use super::token::Literal::{Bool, Float, Isize, None};
+        use super::token_type::TokenType::{Bang, Minus, Tilde};
+
+        let right = self.evaluate(right)?;
+        if let Object::Instance(instance) = &right {
+            if let Some(result) = self.call_operator_method(instance, operator, Vec::new())? {
+                return Ok(result);
+            }
+        }
+        match (operator.token_type, right) {
+            (Minus, Object::Literal(lit)) => match lit {
+                Isize(r) => Interpreter::checked_integer(operator, r.checked_neg()),
+                Float(r) => Ok(Object::Literal(Float(-r))),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operand must be a number."),
+                )),
+            },
+            (Bang, right) => Ok(Object::Literal(Bool(!self.is_truthy(right)))),
+            (Tilde, Object::Literal(lit)) => match lit {
+                Isize(r) => Ok(Object::Literal(Isize(!r))),
+                _ => Err(Error::RuntimeError(
+                    operator.clone(),
+                    String::from("Operand must be an integer."),
+                )),
+            },
+            (Minus, _) => Err(Error::RuntimeError(
+                operator.clone(),
+                String::from("Operand must be a number."),
+            )),
+            (Tilde, _) => Err(Error::RuntimeError(
+                operator.clone(),
+                String::from("Operand must be an integer."),
+            )),
+            _ => Ok(Object::Literal(None)),
+        }
+    }
+
+    fn visit_variable(&mut self, name: &Token) -> Result<Object> {
+        let expr = Expr::Variable { name: name.clone() };
+        self.look_up_variable(name, &expr)
+    }
+
+    fn visit_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<Object> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        self.binary_operation(left, operator, right)
+    }
+
+    fn visit_conditional(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Result<Object> {
+        let evaluated_condition = self.evaluate(condition)?;
+        if self.is_truthy(evaluated_condition) {
+            self.evaluate(then_branch)
+        } else {
+            self.evaluate(else_branch)
+        }
+    }
+
     fn visit_call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Result<Object> {
         let callee = self.evaluate(callee)?;
         let mut evaluated_args = vec![];
@@ -277,9 +1044,9 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         ),
                     ));
                 }
-                Ok(func.call(self, evaluated_args)?)
+                Ok(func.call(self, paren, evaluated_args)?)
             }
-            Object::Clock(func) => {
+            Object::Native(func) => {
                 if evaluated_args.len() != func.arity() {
                     return Err(Error::RuntimeError(
                         paren.clone(),
@@ -290,7 +1057,7 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         ),
                     ));
                 }
-                Ok(func.call(self, evaluated_args)?)
+                Ok(func.call(self, paren, evaluated_args)?)
             }
             Object::Class(class) => {
                 if evaluated_args.len() != class.arity() {
@@ -303,7 +1070,7 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                         ),
                     ));
                 }
-                Ok(class.call(self, evaluated_args)?)
+                Ok(class.call(self, paren, evaluated_args)?)
             }
             _ => Err(Error::RuntimeError(
                 paren.clone(),
@@ -313,13 +1080,7 @@ impl expr::Visitor<Result<Object>> for Interpreter {
     }
     fn visit_get(&mut self, object: &Expr, name: &Token) -> Result<Object> {
         let evaluated_object = self.evaluate(object)?;
-        match evaluated_object {
-            Object::Instance(mut instance) => Ok(instance.get(name)?),
-            _ => Err(Error::RuntimeError(
-                name.clone(),
-                String::from("Only instances have properties."),
-            )),
-        }
+        self.get_property(evaluated_object, name)
     }
 
     fn visit_literal(&mut self, expr: &Literal) -> Result<Object> {
@@ -341,17 +1102,15 @@ impl expr::Visitor<Result<Object>> for Interpreter {
     }
     fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<Object> {
         let evaluated_object = self.evaluate(object)?;
-        match evaluated_object {
-            Object::Instance(mut instance) => {
-                let evaluated_value = self.evaluate(value)?;
-                instance.set(name, &evaluated_value);
-                Ok(evaluated_value)
-            }
-            _ => Err(Error::RuntimeError(
-                name.clone(),
-                String::from("Only instances have fields."),
-            )),
+        if let Object::Instance(_) | Object::Class(_) = evaluated_object {
+            let evaluated_value = self.evaluate(value)?;
+            self.set_property(evaluated_object, name, &evaluated_value)?;
+            return Ok(evaluated_value);
         }
+        Err(Error::RuntimeError(
+            name.clone(),
+            String::from("Only instances and classes have fields."),
+        ))
     }
     fn visit_super(&mut self, keyword: &Token, method: &Token) -> Result<Object> {
         let distance = self
@@ -360,11 +1119,11 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                 keyword: keyword.clone(),
                 method: method.clone(),
             })
-            .expect(&format!("super found on locals: {:?}", self.locals));
+            .unwrap_or_else(|| panic!("super found on locals: {:?}", self.locals));
         let object_super = self
             .environment
             .borrow()
-            .get_at(distance.clone(), "super".to_string())?;
+            .get_at(*distance, "super".to_string())?;
         if let Object::Class(superclass) = object_super {
             let this = self
                 .environment
@@ -376,7 +1135,17 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                 }
                 return Err(Error::RuntimeError(
                     method.clone(),
-                    format!("Undefined propertiesperty '{}' '.", method.lexeme),
+                    format!("Undefined property '{}'.", method.lexeme),
+                ));
+            }
+            // Note: inside a static method `this` is the class
+            if let Object::Class(class) = this {
+                if let Some(method) = superclass.find_class_method(method.lexeme.clone()) {
+                    return Ok(Object::Func(method.bind_class(class)));
+                }
+                return Err(Error::RuntimeError(
+                    method.clone(),
+                    format!("Undefined property '{}'.", method.lexeme),
                 ));
             }
             return Err(Error::RuntimeError(
@@ -402,16 +1171,79 @@ impl expr::Visitor<Result<Object>> for Interpreter {
             name: name.clone(),
             value: Box::new(value.clone()),
         };
-        match self.locals.get(&expr) {
-            Some(distance) => {
-                self.environment.borrow_mut().assign_at(
-                    distance.clone(),
-                    name.clone(),
-                    evaluated_value.clone(),
-                );
-            }
-            None => self.globals.borrow_mut().assign(name, &evaluated_value)?,
+        self.assign_variable(name, &expr, evaluated_value.clone())?;
+        Ok(evaluated_value)
+    }
+
+    fn visit_compound_assign(
+        &mut self,
+        target: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Result<Object> {
+        let place = self.evaluate_place(target)?;
+        let current = self.read_place(target, &place)?;
+        let evaluated_value = self.evaluate(value)?;
+        let result = self.binary_operation(
+            current,
+            &Interpreter::arithmetic_operator(operator),
+            evaluated_value,
+        )?;
+        self.write_place(target, place, result.clone())?;
+        Ok(result)
+    }
+
+    fn visit_increment(
+        &mut self,
+        target: &Expr,
+        operator: &Token,
+        postfix: bool,
+    ) -> Result<Object> {
+        let place = self.evaluate_place(target)?;
+        let current = self.read_place(target, &place)?;
+        let result = self.binary_operation(
+            current.clone(),
+            &Interpreter::arithmetic_operator(operator),
+            Object::Literal(Literal::Isize(1)),
+        )?;
+        self.write_place(target, place, result.clone())?;
+        if postfix {
+            Ok(current)
+        } else {
+            Ok(result)
         }
+    }
+
+    fn visit_list(&mut self, elements: &[Expr]) -> Result<Object> {
+        let mut evaluated_elements = vec![];
+        for element in elements {
+            evaluated_elements.push(self.evaluate(element)?);
+        }
+        Ok(Object::List(Rc::new(RefCell::new(evaluated_elements))))
+    }
+
+    fn visit_index(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Result<Object> {
+        let evaluated_object = self.evaluate(object)?;
+        let evaluated_index = self.evaluate(index)?;
+        self.index_get(&evaluated_object, &evaluated_index, bracket)
+    }
+
+    fn visit_index_set(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Object> {
+        let evaluated_object = self.evaluate(object)?;
+        let evaluated_index = self.evaluate(index)?;
+        let evaluated_value = self.evaluate(value)?;
+        self.index_set(
+            &evaluated_object,
+            &evaluated_index,
+            bracket,
+            evaluated_value.clone(),
+        )?;
         Ok(evaluated_value)
     }
 }
@@ -420,7 +1252,7 @@ impl stmt::Visitor<Result<()>> for Interpreter {
     fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<()> {
         let result = self.evaluate(expression)?;
         if self.environment.borrow().is_repl {
-            println!("{}", result);
+            println!("{}", self.stringify(&result)?);
         }
         Ok(())
     }
@@ -434,18 +1266,30 @@ impl stmt::Visitor<Result<()>> for Interpreter {
         if self.is_truthy(evaluated) {
             self.execute(then_branch)?
         }
-        match else_branch {
-            Some(eb) => self.execute(&*eb)?,
-            None => {}
+        if let Some(eb) = else_branch {
+            self.execute(eb)?
         }
         Ok(())
     }
-    fn visit_print_stmt(&mut self, expression: &Expr) -> Result<()> {
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) -> Result<()> {
         let value = self.evaluate(expression)?;
-        println!("{}", value);
+        let text = self.stringify(&value)?;
+        match &self.output {
+            Some(output) => {
+                let mut output = output.borrow_mut();
+                output.push_str(&text);
+                output.push('\n');
+            }
+            None => println!("{}", text),
+        }
         Ok(())
     }
-    fn visit_var_stmt(&mut self, name: &Token, initializer: &Expr) -> Result<()> {
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        _type_annotation: &Option<TypeAnnotation>,
+        initializer: &Expr,
+    ) -> Result<()> {
         let value = self.evaluate(initializer)?;
         self.environment
             .borrow_mut()
@@ -464,12 +1308,16 @@ impl stmt::Visitor<Result<()>> for Interpreter {
         &mut self,
         name: &Token,
         super_class: &Option<Expr>,
+        traits: &[Expr],
+        methods: &[Stmt],
         class_methods: &[Stmt],
+        getters: &[Stmt],
+        setters: &[Stmt],
+        sealed: bool,
     ) -> Result<()> {
-        use super::callable::LoxFunction;
         let evaluated_super_class = match super_class {
             Some(sc) => match self.evaluate(sc)? {
-                Object::Class(lc) => Some(Box::new(lc)),
+                Object::Class(lc) => Some(lc),
                 _ => {
                     if let Expr::Variable { name: scname } = sc {
                         return Err(Error::RuntimeError(
@@ -482,6 +1330,39 @@ impl stmt::Visitor<Result<()>> for Interpreter {
             },
             None => None,
         };
+        let mut trait_methods: HashMap<String, (LoxFunction, String)> = HashMap::new();
+        for used_trait in traits {
+            let trait_name = match used_trait {
+                Expr::Variable { name } => name,
+                _ => unreachable!(),
+            };
+            let lox_trait = match self.evaluate(used_trait)? {
+                Object::Trait(lox_trait) => lox_trait,
+                _ => {
+                    return Err(Error::RuntimeError(
+                        trait_name.clone(),
+                        format!("'{}' is not a trait.", trait_name.lexeme),
+                    ))
+                }
+            };
+            for (method_name, method) in lox_trait.methods.iter() {
+                let previous = trait_methods.insert(
+                    method_name.clone(),
+                    (method.clone(), lox_trait.name.clone()),
+                );
+                if let Some((_, other)) = previous {
+                    if !Interpreter::declares_method(methods, method_name) {
+                        return Err(Error::RuntimeError(
+                            trait_name.clone(),
+                            format!(
+                                "Method '{}' is provided by both '{}' and '{}', override it in the class.",
+                                method_name, other, lox_trait.name
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
         self.environment
             .borrow_mut()
             .define(name.lexeme.clone(), &Object::Literal(Literal::None));
@@ -491,34 +1372,28 @@ impl stmt::Visitor<Result<()>> for Interpreter {
             self.environment = Rc::new(RefCell::new(new_env));
             self.environment.borrow_mut().define(
                 "super".to_string(),
-                &Object::Class(
-                    *evaluated_super_class
-                        .clone()
+                &Object::Class(Rc::clone(
+                    evaluated_super_class
+                        .as_ref()
                         .expect("superclass does not exist."),
-                ),
+                )),
             );
         }
-        let mut methods: HashMap<String, LoxFunction> = HashMap::new();
-        for method in class_methods {
-            match method {
-                Stmt::Function {
-                    name: func_name,
-                    params,
-                    body,
-                } => {
-                    let function = LoxFunction::new(
-                        func_name.clone(),
-                        params.to_vec(),
-                        body.to_vec(),
-                        Rc::clone(&self.environment),
-                        func_name.lexeme == "init",
-                    );
-                    methods.insert(func_name.lexeme.clone(), function);
-                }
-                _ => unreachable!(),
-            }
-        }
-        let klass = LoxClass::new(name.lexeme.clone(), evaluated_super_class.clone(), methods);
+        // Note: trait methods are copied in first so the class's own methods override them
+        let mut own_methods: HashMap<String, LoxFunction> = trait_methods
+            .into_iter()
+            .map(|(method_name, (method, _))| (method_name, method))
+            .collect();
+        own_methods.extend(self.method_table(methods, true));
+        let klass = LoxClass::new(
+            name.lexeme.clone(),
+            evaluated_super_class.clone(),
+            own_methods,
+            self.method_table(class_methods, false),
+            self.method_table(getters, false),
+            self.method_table(setters, false),
+            sealed,
+        );
         if evaluated_super_class.is_some() {
             let enclosing = self
                 .environment
@@ -530,7 +1405,7 @@ impl stmt::Visitor<Result<()>> for Interpreter {
         }
         self.environment
             .borrow_mut()
-            .assign(name, &Object::Class(klass))?;
+            .assign(name, &Object::Class(Rc::new(klass)))?;
         Ok(())
     }
     fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<()> {
@@ -544,12 +1419,26 @@ impl stmt::Visitor<Result<()>> for Interpreter {
         }
         Ok(())
     }
-    fn visit_function_stmt(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> Result<()> {
+    fn visit_trait_stmt(&mut self, name: &Token, methods: &[Stmt]) -> Result<()> {
+        let lox_trait = LoxTrait::new(name.lexeme.clone(), self.method_table(methods, false));
+        self.environment
+            .borrow_mut()
+            .define(name.lexeme.clone(), &Object::Trait(Rc::new(lox_trait)));
+        Ok(())
+    }
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        _param_types: &[Option<TypeAnnotation>],
+        _return_type: &Option<TypeAnnotation>,
+        body: &[Stmt],
+    ) -> Result<()> {
         use super::callable::LoxFunction;
         let function = Object::Func(LoxFunction::new(
             name.clone(),
-            params.to_vec(),
-            body.to_vec(),
+            Rc::new(params.to_vec()),
+            Rc::new(body.to_vec()),
             Rc::clone(&self.environment),
             false,
         ));
@@ -558,13 +1447,56 @@ impl stmt::Visitor<Result<()>> for Interpreter {
             .define(name.lexeme.clone(), &function);
         Ok(())
     }
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> Result<()> {
+        let evaluated_value = self.evaluate(value)?;
+        if let Object::Instance(instance) = &evaluated_value {
+            // Note: `Error(message)` leaves line as nil until it is thrown
+            let mut fields = instance.fields.borrow_mut();
+            if let Some(Object::Literal(Literal::None)) = fields.get("line") {
+                fields.insert(
+                    "line".to_string(),
+                    Object::Literal(Literal::Isize(keyword.line as isize)),
+                );
+            }
+        }
+        Err(Error::Throw(evaluated_value, keyword.line))
+    }
+    fn visit_try_stmt(
+        &mut self,
+        try_branch: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_branch: &[Stmt],
+        finally_branch: &Option<Vec<Stmt>>,
+    ) -> Result<()> {
+        let is_repl = self.environment.borrow().is_repl;
+        let mut result = self.execute_block(
+            try_branch,
+            Environment::new(Some(Rc::clone(&self.environment)), is_repl),
+        );
+        if let (Some(name), Err(error)) = (catch_name, &result) {
+            result = match self.catchable_value(error.clone()) {
+                Ok(value) => {
+                    let env = Environment::new(Some(Rc::clone(&self.environment)), is_repl);
+                    env.define(name.lexeme.clone(), &value);
+                    self.execute_block(catch_branch, env)
+                }
+                Err(e) => Err(e),
+            };
+        }
+        if let Some(fb) = finally_branch {
+            self.execute_block(
+                fb,
+                Environment::new(Some(Rc::clone(&self.environment)), is_repl),
+            )?;
+        }
+        result
+    }
     fn visit_return_stmt(&mut self, _keyword: &Token, v: &Expr) -> Result<()> {
         let evaluated_value = match v {
-            Expr::Literal { value } => match value {
-                Literal::None => Object::Literal(Literal::None),
-                _ => self.evaluate(&v)?,
-            },
-            _ => self.evaluate(&v)?,
+            Expr::Literal {
+                value: Literal::None,
+            } => Object::Literal(Literal::None),
+            _ => self.evaluate(v)?,
         };
         Err(Error::Return(evaluated_value))
     }
@@ -4,12 +4,19 @@ use super::token_type::TokenType;
 use std::collections::HashMap;
 
 pub struct Lexer {
-    source: String,
+    // Note: chars rather than bytes, so positions are character offsets
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    start_column: usize,
     keywords: HashMap<String, TokenType>,
+    // Note: only the formatter wants comments, the parser never sees them
+    preserve_comments: bool,
+    // Note: line and message of every error, they are also logged as they are found
+    pub errors: Vec<(usize, String)>,
 }
 
 impl Lexer {
@@ -31,19 +38,36 @@ impl Lexer {
         keywords.insert(String::from("true"), TokenType::True);
         keywords.insert(String::from("var"), TokenType::Var);
         keywords.insert(String::from("while"), TokenType::While);
+        keywords.insert(String::from("throw"), TokenType::Throw);
+        keywords.insert(String::from("try"), TokenType::Try);
+        keywords.insert(String::from("catch"), TokenType::Catch);
+        keywords.insert(String::from("finally"), TokenType::Finally);
+        keywords.insert(String::from("is"), TokenType::Is);
+        keywords.insert(String::from("trait"), TokenType::Trait);
+        keywords.insert(String::from("with"), TokenType::With);
         Lexer {
-            source,
+            source: source.chars().collect(),
             tokens: vec![],
             start: 0,
             current: 0,
-            line: 0,
+            line: 1,
+            line_start: 0,
+            start_column: 1,
             keywords,
+            preserve_comments: false,
+            errors: Vec::new(),
         }
     }
 
+    pub fn preserving_comments(mut self) -> Lexer {
+        self.preserve_comments = true;
+        self
+    }
+
     pub fn tokenize_all(&mut self) -> Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_column = self.current - self.line_start + 1;
             self.tokenize()
         }
         self.tokens.push(Token::new(
@@ -51,6 +75,7 @@ impl Lexer {
             String::from(""),
             Literal::None,
             self.line,
+            self.current - self.line_start + 1,
         ));
         self.tokens.clone()
     }
@@ -63,12 +88,24 @@ impl Lexer {
             ')' => self.add_token_without_literal(TokenType::RightParen),
             '{' => self.add_token_without_literal(TokenType::LeftBrace),
             '}' => self.add_token_without_literal(TokenType::RightBrace),
+            '[' => self.add_token_without_literal(TokenType::LeftBracket),
+            ']' => self.add_token_without_literal(TokenType::RightBracket),
             ',' => self.add_token_without_literal(TokenType::Comma),
             '.' => self.add_token_without_literal(TokenType::Dot),
-            '-' => self.add_token_without_literal(TokenType::Minus),
-            '+' => self.add_token_without_literal(TokenType::Plus),
             ';' => self.add_token_without_literal(TokenType::Semicolon),
-            '*' => self.add_token_without_literal(TokenType::Star),
+            '?' => self.add_token_without_literal(TokenType::Question),
+            ':' => self.add_token_without_literal(TokenType::Colon),
+            '&' => self.add_token_without_literal(TokenType::Ampersand),
+            '|' => self.add_token_without_literal(TokenType::Pipe),
+            '^' => self.add_token_without_literal(TokenType::Caret),
+            '~' => {
+                let next_token = if self.match_to_expected('/') {
+                    TokenType::TildeSlash
+                } else {
+                    TokenType::Tilde
+                };
+                self.add_token_without_literal(next_token)
+            }
             // Multi or single lexemes
             '!' => {
                 let next_token = if self.match_to_expected('=') {
@@ -86,9 +123,49 @@ impl Lexer {
                 };
                 self.add_token_without_literal(next_token)
             }
+            '-' => {
+                let next_token = if self.match_to_expected('=') {
+                    TokenType::MinusEqual
+                } else if self.match_to_expected('-') {
+                    TokenType::MinusMinus
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token_without_literal(next_token)
+            }
+            '+' => {
+                let next_token = if self.match_to_expected('=') {
+                    TokenType::PlusEqual
+                } else if self.match_to_expected('+') {
+                    TokenType::PlusPlus
+                } else {
+                    TokenType::Plus
+                };
+                self.add_token_without_literal(next_token)
+            }
+            '%' => {
+                let next_token = if self.match_to_expected('=') {
+                    TokenType::PercentEqual
+                } else {
+                    TokenType::Percent
+                };
+                self.add_token_without_literal(next_token)
+            }
+            '*' => {
+                let next_token = if self.match_to_expected('*') {
+                    TokenType::StarStar
+                } else if self.match_to_expected('=') {
+                    TokenType::StarEqual
+                } else {
+                    TokenType::Star
+                };
+                self.add_token_without_literal(next_token)
+            }
             '<' => {
                 let next_token = if self.match_to_expected('=') {
                     TokenType::LessEqual
+                } else if self.match_to_expected('<') {
+                    TokenType::LessLess
                 } else {
                     TokenType::Less
                 };
@@ -97,6 +174,8 @@ impl Lexer {
             '>' => {
                 let next_token = if self.match_to_expected('=') {
                     TokenType::GreaterEqual
+                } else if self.match_to_expected('>') {
+                    TokenType::GreaterGreater
                 } else {
                     TokenType::Greater
                 };
@@ -108,6 +187,11 @@ impl Lexer {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    if self.preserve_comments {
+                        self.add_token_without_literal(TokenType::Comment);
+                    }
+                } else if self.match_to_expected('=') {
+                    self.add_token_without_literal(TokenType::SlashEqual);
                 } else {
                     self.add_token_without_literal(TokenType::Slash);
                 }
@@ -115,17 +199,32 @@ impl Lexer {
 
             // Ignore whitespaces
             ' ' | '\r' | '\t' => {}
-            '\n' => self.line += 1,
+            '\n' => self.new_line(),
             '"' => self.string(),
             '0'..='9' => self.number(),
             'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
-            _ => lexer_error(self.line, "Unexpected character."),
+            '#' if matches!(self.peek(), 'a'..='z' | 'A'..='Z' | '_') => self.private_identifier(),
+            _ => self.error("Unexpected character."),
         }
     }
 
+    fn error(&mut self, message: &str) {
+        self.errors.push((self.line, String::from(message)));
+        lexer_error(self.line, message);
+    }
+
     fn advance(&mut self) -> char {
         self.current += 1;
-        self.source.chars().nth(self.current - 1).unwrap()
+        self.source[self.current - 1]
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        self.source[start..end].iter().collect()
     }
 
     fn is_at_end(&self) -> bool {
@@ -137,12 +236,13 @@ impl Lexer {
     }
 
     fn add_token_with_literal(&mut self, token_type: TokenType, literal: Literal) {
-        let text = self.source.get(self.start..self.current).unwrap();
+        let text = self.text(self.start, self.current);
         self.tokens.push(Token::new(
             token_type,
-            String::from(text),
+            text,
             literal,
             self.line,
+            self.start_column,
         ))
     }
 
@@ -150,7 +250,7 @@ impl Lexer {
         if self.is_at_end() {
             return false;
         }
-        if self.source.chars().nth(self.current).unwrap() != expected {
+        if self.source[self.current] != expected {
             return false;
         };
         self.current += 1;
@@ -161,63 +261,73 @@ impl Lexer {
         if self.is_at_end() {
             return '\0';
         }
-        self.source.chars().nth(self.current).unwrap()
+        self.source[self.current]
     }
 
     fn peek_next(&mut self) -> char {
         if self.current + 1 >= self.source.len() {
             return '\n';
         }
-        self.source.chars().nth(self.current + 1).unwrap()
+        self.source[self.current + 1]
     }
 
     fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
             if self.peek() == '\n' {
-                self.line += 1
+                self.advance();
+                self.new_line();
+                continue;
             }
             self.advance();
         }
 
         // Unterminated string.
         if self.is_at_end() {
-            lexer_error(self.line, "Unterminated string.");
+            self.error("Unterminated string.");
             return;
         }
 
         // The closing ".
         self.advance();
 
-        let value = String::from(self.source.get(self.start + 1..self.current - 1).unwrap());
+        let value = self.text(self.start + 1, self.current - 1);
         self.add_token_with_literal(TokenType::String, Literal::String(value));
     }
 
     fn number(&mut self) {
-        while ('0'..='9').contains(&self.peek()) {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
 
         // Look for a fractional part.
-        if self.peek() == '.' && ('0'..='9').contains(&self.peek_next()) {
+        let mut is_float = false;
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             // Consume the "."
             self.advance();
+            is_float = true;
 
-            while ('0'..='9').contains(&self.peek()) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
         }
 
-        let target_literal = self.source.get(self.start..self.current).unwrap();
-        let parsed_usize = target_literal.parse::<isize>();
-        let parsed_float = target_literal.parse::<f64>();
-
-        let literal = if parsed_usize.is_ok() {
-            Literal::Isize(parsed_usize.ok().unwrap())
-        } else if parsed_float.is_ok() {
-            Literal::Float(parsed_float.ok().unwrap())
+        // Note: a literal is a float exactly when it is written with a fractional part
+        let target_literal = self.text(self.start, self.current);
+        let literal = if is_float {
+            Literal::Float(
+                target_literal
+                    .parse::<f64>()
+                    .expect("digits with a fractional part should be a float."),
+            )
         } else {
-            lexer_error(self.line, "Unexpected character.");
-            panic!("")
+            match target_literal.parse::<isize>() {
+                Ok(i) => Literal::Isize(i),
+                // Note: the token is still added so the parser does not report a missing operand
+                Err(_) => {
+                    self.error("Integer literal is too large.");
+                    Literal::Isize(0)
+                }
+            }
         };
         self.add_token_with_literal(TokenType::Number, literal)
     }
@@ -226,20 +336,23 @@ impl Lexer {
         while is_alpha_numeric(self.peek()) {
             self.advance();
         }
-        let range = self.start..self.current;
-        let text = self.source.get(range).unwrap();
+        let text = self.text(self.start, self.current);
         // Memo: using match instead of HashMap is more perfomant?
-        let keywords = self.keywords.clone();
-        let token = keywords.get(text).unwrap_or(&TokenType::Identifier);
+        let token = *self.keywords.get(&text).unwrap_or(&TokenType::Identifier);
+
+        self.add_token_without_literal(token);
+    }
 
-        self.add_token_without_literal(token.clone());
+    // Note: `#name` is only valid as a property name, the lexeme keeps the '#'
+    fn private_identifier(&mut self) {
+        while is_alpha_numeric(self.peek()) {
+            self.advance();
+        }
+        self.add_token_without_literal(TokenType::PrivateIdentifier);
     }
 }
 
 // Helpers
 fn is_alpha_numeric(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '_' | '0'..='9' => true,
-        _ => false,
-    }
+    matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9')
 }
@@ -0,0 +1,24 @@
+// pub mod ast_printer;
+pub mod callable;
+pub mod debugger;
+pub mod environment;
+pub mod error;
+pub mod expr;
+pub mod formatter;
+pub mod interpreter;
+pub mod lexer;
+pub mod lint;
+pub mod lox_class;
+pub mod lox_instance;
+pub mod lox_trait;
+pub mod lsp;
+pub mod native;
+pub mod object;
+pub mod parser;
+pub mod profiler;
+pub mod resolver;
+pub mod stmt;
+pub mod token;
+pub mod token_type;
+pub mod type_checker;
+pub mod types;
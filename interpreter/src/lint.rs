@@ -0,0 +1,628 @@
+use super::callable::LoxCallable;
+use super::environment::Environment;
+use super::expr::Expr;
+use super::expr::{Acceptor as ExprAcceptor, Visitor as ExprVisitor};
+use super::lexer::Lexer;
+use super::object::Object;
+use super::resolver::{SymbolKind, Symbols};
+use super::stmt::{Acceptor as StmtAcceptor, Stmt, Visitor as StmtVisitor};
+use super::token::Literal;
+use super::token::Token;
+use super::token_type::TokenType;
+use super::types::TypeAnnotation;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// Note: the IDs are part of the suppression comments people write, never rename them
+pub const UNUSED_LOCAL: &str = "unused-local";
+pub const UNUSED_PARAMETER: &str = "unused-parameter";
+pub const SHADOWED_VARIABLE: &str = "shadowed-variable";
+pub const UNREACHABLE_CODE: &str = "unreachable-code";
+pub const UNDECLARED_GLOBAL: &str = "undeclared-global";
+pub const WRONG_ARITY: &str = "wrong-arity";
+pub const UNASSIGNED_FIELD: &str = "unassigned-field";
+
+const SUPPRESSION_MARKER: &str = "lint-ignore";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub id: &'static str,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] warning[{}]: {}",
+            self.line, self.id, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Arity {
+    Exactly(usize),
+    // Note: a class without `init` takes the arguments of its superclass
+    SameAs(String),
+}
+
+#[derive(Debug, Default)]
+struct ClassInfo {
+    super_class: Option<String>,
+    traits: Vec<String>,
+    members: HashSet<String>,
+}
+
+// Warnings for code which runs but is probably wrong. Which declaration a name refers to comes
+// from the Symbols of the Resolver, so it expects a program the Resolver has accepted and never
+// fails.
+#[derive(Debug)]
+pub struct Linter {
+    // Note: every declaration and use of a name with the index of its declaration
+    declarations: HashMap<Token, usize>,
+    kinds: Vec<SymbolKind>,
+    // Note: where each local is first assigned, its arity is unknown from there on
+    assigned_at: HashMap<usize, (usize, usize)>,
+    // Note: local functions and classes with their arity
+    arities: HashMap<usize, Arity>,
+    // Note: natives and prelude globals with their arity, plus the top-level declarations
+    globals: HashMap<String, Option<Arity>>,
+    reassigned_globals: HashSet<String>,
+    // Note: checked at the end since a global function may be called before it is declared
+    global_calls: Vec<(Token, usize)>,
+    classes: HashMap<String, ClassInfo>,
+    traits: HashMap<String, HashSet<String>>,
+    // Note: the class whose methods are being linted, None inside a trait
+    current_class: Vec<Option<String>>,
+    field_reads: Vec<(String, Token)>,
+    assigned_properties: HashSet<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    pub fn new(globals: &Environment) -> Linter {
+        let globals = globals
+            .values
+            .borrow()
+            .iter()
+            .map(|(name, value)| {
+                let arity = match value {
+                    Object::Native(function) => Some(Arity::Exactly(function.arity())),
+                    Object::Func(function) => Some(Arity::Exactly(function.arity())),
+                    Object::Class(class) => Some(Arity::Exactly(class.arity())),
+                    _ => None,
+                };
+                (name.clone(), arity)
+            })
+            .collect();
+        Linter {
+            declarations: HashMap::new(),
+            kinds: Vec::new(),
+            assigned_at: HashMap::new(),
+            arities: HashMap::new(),
+            globals,
+            reassigned_globals: HashSet::new(),
+            global_calls: Vec::new(),
+            classes: HashMap::new(),
+            traits: HashMap::new(),
+            current_class: Vec::new(),
+            field_reads: Vec::new(),
+            assigned_properties: HashSet::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    // Returns the warnings sorted by line, leaving out the suppressed ones.
+    pub fn lint(mut self, statements: &[Stmt], symbols: &Symbols, source: &str) -> Vec<Warning> {
+        self.check_symbols(symbols);
+        self.declare_globals(statements);
+        self.lint_statements(statements);
+        self.check_global_calls();
+        self.check_field_reads();
+
+        let suppressions = suppressions(source);
+        let mut warnings: Vec<Warning> = self
+            .warnings
+            .into_iter()
+            .filter(|warning| match suppressions.get(&warning.line) {
+                Some(ids) => !ids.is_empty() && !ids.iter().any(|id| id == warning.id),
+                None => true,
+            })
+            .collect();
+        warnings.sort_by_key(|warning| warning.line);
+        warnings
+    }
+
+    fn warn(&mut self, id: &'static str, line: usize, message: String) {
+        self.warnings.push(Warning { id, line, message });
+    }
+
+    fn declare_globals(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            let (name, arity) = match statement {
+                Stmt::Var { name, .. } => (name, None),
+                Stmt::Function { name, params, .. } => (name, Some(Arity::Exactly(params.len()))),
+                Stmt::Class {
+                    name,
+                    super_class,
+                    traits,
+                    methods,
+                    ..
+                } => (name, class_arity(super_class, traits, methods)),
+                Stmt::Trait { name, .. } => (name, None),
+                _ => continue,
+            };
+            // Note: a name declared twice may refer to either declaration
+            if self.globals.contains_key(&name.lexeme) {
+                self.reassigned_globals.insert(name.lexeme.clone());
+            }
+            self.globals.insert(name.lexeme.clone(), arity);
+        }
+    }
+
+    fn lint_statements(&mut self, statements: &[Stmt]) {
+        // Note: only the first dead statement is reported
+        let mut terminated = false;
+        for (index, statement) in statements.iter().enumerate() {
+            if let (false, Some(keyword), Some(next)) =
+                (terminated, exits(statement), statements.get(index + 1))
+            {
+                terminated = true;
+                self.warn(
+                    UNREACHABLE_CODE,
+                    next.line().unwrap_or(keyword.line),
+                    format!("Code after '{}' is never run.", keyword.lexeme),
+                );
+            }
+            statement.accept(self);
+        }
+    }
+
+    fn lint_expr(&mut self, expr: &Expr) {
+        expr.accept(self)
+    }
+
+    // Reports the locals which are never read and those hiding a local of an enclosing scope.
+    fn check_symbols(&mut self, symbols: &Symbols) {
+        self.kinds = symbols.kinds.clone();
+        let mut read = HashSet::new();
+        for (index, name) in symbols.declarations.iter().enumerate() {
+            self.declarations.insert(name.clone(), index);
+        }
+        for (index, (name, declaration)) in symbols.references.iter().enumerate() {
+            self.declarations.insert(name.clone(), *declaration);
+            if !symbols.assignments.contains(&index) {
+                read.insert(*declaration);
+                continue;
+            }
+            let position = (name.line, name.column);
+            let assigned_at = self.assigned_at.entry(*declaration).or_insert(position);
+            *assigned_at = (*assigned_at).min(position);
+        }
+
+        for (declaration, shadowed) in &symbols.shadowed {
+            let name = &symbols.declarations[*declaration];
+            self.warn(
+                SHADOWED_VARIABLE,
+                name.line,
+                format!(
+                    "'{}' shadows the variable declared on line {}.",
+                    name.lexeme, symbols.declarations[*shadowed].line
+                ),
+            );
+        }
+
+        for (index, (name, kind)) in symbols
+            .declarations
+            .iter()
+            .zip(symbols.kinds.iter())
+            .enumerate()
+        {
+            if read.contains(&index) || name.lexeme.starts_with('_') {
+                continue;
+            }
+            match kind {
+                SymbolKind::Local => self.warn(
+                    UNUSED_LOCAL,
+                    name.line,
+                    format!("Local variable '{}' is never used.", name.lexeme),
+                ),
+                SymbolKind::Parameter => self.warn(
+                    UNUSED_PARAMETER,
+                    name.line,
+                    format!("Parameter '{}' is never used.", name.lexeme),
+                ),
+                SymbolKind::Global | SymbolKind::Catch => {}
+            }
+        }
+    }
+
+    // Note: the index of the local declaration a name refers to, None for globals
+    fn local(&self, name: &Token) -> Option<usize> {
+        let declaration = *self.declarations.get(name)?;
+        match self.kinds[declaration] {
+            SymbolKind::Global => None,
+            _ => Some(declaration),
+        }
+    }
+
+    // Note: a call before the first assignment still calls the declared function
+    fn local_arity(&self, declaration: usize, callee: &Token) -> Option<usize> {
+        if let Some(&assigned_at) = self.assigned_at.get(&declaration) {
+            if assigned_at < (callee.line, callee.column) {
+                return None;
+            }
+        }
+        match self.arities.get(&declaration)? {
+            Arity::Exactly(n) => Some(*n),
+            Arity::SameAs(_) => None,
+        }
+    }
+
+    fn assign_variable(&mut self, name: &Token) {
+        if self.local(name).is_some() {
+            return;
+        }
+        if self.globals.contains_key(&name.lexeme) {
+            self.reassigned_globals.insert(name.lexeme.clone());
+        } else {
+            self.warn(
+                UNDECLARED_GLOBAL,
+                name.line,
+                format!("Assignment to undeclared global '{}'.", name.lexeme),
+            );
+        }
+    }
+
+    fn lint_methods(&mut self, methods: &[Stmt]) {
+        for method in methods {
+            if let Stmt::Function { body, .. } = method {
+                self.lint_statements(body);
+            }
+        }
+    }
+
+    fn global_arity(&self, name: &str, depth: usize) -> Option<usize> {
+        if self.reassigned_globals.contains(name) || depth > self.globals.len() {
+            return None;
+        }
+        match self.globals.get(name)? {
+            Some(Arity::Exactly(n)) => Some(*n),
+            Some(Arity::SameAs(super_class)) => self.global_arity(super_class, depth + 1),
+            None => None,
+        }
+    }
+
+    fn check_arity(&mut self, callee: &Token, expected: usize, actual: usize) {
+        if expected != actual {
+            self.warn(
+                WRONG_ARITY,
+                callee.line,
+                format!(
+                    "Call to '{}' expected {} arguments but got {}.",
+                    callee.lexeme, expected, actual
+                ),
+            );
+        }
+    }
+
+    fn check_global_calls(&mut self) {
+        for (callee, actual) in std::mem::take(&mut self.global_calls) {
+            if let Some(expected) = self.global_arity(&callee.lexeme, 0) {
+                self.check_arity(&callee, expected, actual);
+            }
+        }
+    }
+
+    // Note: collects the members of a class and everything it inherits, None when part of
+    // the hierarchy is declared outside this file
+    fn members(&self, class: &str, depth: usize) -> Option<HashSet<String>> {
+        let info = self.classes.get(class)?;
+        if depth > self.classes.len() {
+            return None;
+        }
+        let mut members = info.members.clone();
+        for used_trait in &info.traits {
+            members.extend(self.traits.get(used_trait)?.iter().cloned());
+        }
+        if let Some(super_class) = &info.super_class {
+            members.extend(self.members(super_class, depth + 1)?);
+        }
+        Some(members)
+    }
+
+    fn check_field_reads(&mut self) {
+        for (class, field) in std::mem::take(&mut self.field_reads) {
+            if self.assigned_properties.contains(&field.lexeme) {
+                continue;
+            }
+            if let Some(members) = self.members(&class, 0) {
+                if !members.contains(&field.lexeme) {
+                    self.warn(
+                        UNASSIGNED_FIELD,
+                        field.line,
+                        format!(
+                            "Field '{}' is read in class '{}' but never assigned.",
+                            field.lexeme, class
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Note: None when the arity depends on a trait or cannot be known
+fn class_arity(super_class: &Option<Expr>, traits: &[Expr], methods: &[Stmt]) -> Option<Arity> {
+    for method in methods {
+        if let Stmt::Function { name, params, .. } = method {
+            if name.lexeme == "init" {
+                return Some(Arity::Exactly(params.len()));
+            }
+        }
+    }
+    if !traits.is_empty() {
+        return None;
+    }
+    match super_class {
+        Some(Expr::Variable { name }) => Some(Arity::SameAs(name.lexeme.clone())),
+        _ => Some(Arity::Exactly(0)),
+    }
+}
+
+fn exits(statement: &Stmt) -> Option<&Token> {
+    match statement {
+        Stmt::Return { keyword, .. } | Stmt::Throw { keyword, .. } => Some(keyword),
+        _ => None,
+    }
+}
+
+fn names(declarations: &[Stmt]) -> impl Iterator<Item = String> + '_ {
+    declarations
+        .iter()
+        .filter_map(|declaration| match declaration {
+            Stmt::Function { name, .. } => Some(name.lexeme.clone()),
+            _ => None,
+        })
+}
+
+// Finds `// lint-ignore` comments. Followed by IDs, only those lints are suppressed, otherwise
+// all of them. A comment after code applies to its own line, a comment alone on a line applies
+// to the next one. The value is empty when every lint is suppressed.
+pub fn suppressions(source: &str) -> HashMap<usize, Vec<String>> {
+    let mut suppressions = HashMap::new();
+    // Note: the comments come from the lexer, so a `//` inside a string is not one
+    let tokens = Lexer::new(String::from(source))
+        .preserving_comments()
+        .tokenize_all();
+    let mut code_line = 0;
+    for token in tokens {
+        if token.token_type != TokenType::Comment {
+            code_line = token.line;
+            continue;
+        }
+        let comment = token.lexeme.trim_start_matches('/').trim();
+        if !comment.starts_with(SUPPRESSION_MARKER) {
+            continue;
+        }
+        let ids: Vec<String> = comment[SUPPRESSION_MARKER.len()..]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|id| !id.is_empty())
+            .map(String::from)
+            .collect();
+        let line_number = if code_line == token.line {
+            token.line
+        } else {
+            token.line + 1
+        };
+        suppressions.insert(line_number, ids);
+    }
+    suppressions
+}
+
+impl ExprVisitor<()> for Linter {
+    fn visit_binary(&mut self, left: &Expr, _operator: &Token, right: &Expr) {
+        self.lint_expr(left);
+        self.lint_expr(right);
+    }
+    fn visit_conditional(&mut self, condition: &Expr, then_branch: &Expr, else_branch: &Expr) {
+        self.lint_expr(condition);
+        self.lint_expr(then_branch);
+        self.lint_expr(else_branch);
+    }
+    fn visit_grouping(&mut self, expression: &Expr) {
+        self.lint_expr(expression);
+    }
+    fn visit_literal(&mut self, _expr: &Literal) {}
+    fn visit_logical(&mut self, left: &Expr, _operator: &Token, right: &Expr) {
+        self.lint_expr(left);
+        self.lint_expr(right);
+    }
+    fn visit_unary(&mut self, _operator: &Token, right: &Expr) {
+        self.lint_expr(right);
+    }
+    fn visit_variable(&mut self, _name: &Token) {}
+    fn visit_assign(&mut self, name: &Token, value: &Expr) {
+        self.lint_expr(value);
+        self.assign_variable(name);
+    }
+    fn visit_compound_assign(&mut self, target: &Expr, _operator: &Token, value: &Expr) {
+        self.lint_expr(value);
+        self.lint_expr(target);
+        if let Expr::Variable { name } = target {
+            self.assign_variable(name);
+        }
+    }
+    fn visit_increment(&mut self, target: &Expr, _operator: &Token, _postfix: bool) {
+        self.lint_expr(target);
+        if let Expr::Variable { name } = target {
+            self.assign_variable(name);
+        }
+    }
+    fn visit_call(&mut self, callee: &Expr, _paren: &Token, arguments: &[Expr]) {
+        self.lint_expr(callee);
+        for argument in arguments {
+            self.lint_expr(argument);
+        }
+        if let Expr::Variable { name } = callee {
+            match self.local(name) {
+                Some(declaration) => {
+                    if let Some(expected) = self.local_arity(declaration, name) {
+                        self.check_arity(name, expected, arguments.len());
+                    }
+                }
+                None => self.global_calls.push((name.clone(), arguments.len())),
+            }
+        }
+    }
+    fn visit_get(&mut self, object: &Expr, name: &Token) {
+        if let (Expr::This { .. }, Some(Some(class))) = (object, self.current_class.last()) {
+            let read = (class.clone(), name.clone());
+            self.field_reads.push(read);
+        }
+        self.lint_expr(object);
+    }
+    fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) {
+        self.assigned_properties.insert(name.lexeme.clone());
+        self.lint_expr(value);
+        self.lint_expr(object);
+    }
+    fn visit_list(&mut self, elements: &[Expr]) {
+        for element in elements {
+            self.lint_expr(element);
+        }
+    }
+    fn visit_index(&mut self, object: &Expr, _bracket: &Token, index: &Expr) {
+        self.lint_expr(object);
+        self.lint_expr(index);
+    }
+    fn visit_index_set(&mut self, object: &Expr, _bracket: &Token, index: &Expr, value: &Expr) {
+        self.lint_expr(object);
+        self.lint_expr(index);
+        self.lint_expr(value);
+    }
+    fn visit_this(&mut self, _keyword: &Token) {}
+    fn visit_super(&mut self, _keyword: &Token, _method: &Token) {}
+}
+
+impl StmtVisitor<()> for Linter {
+    fn visit_expression_stmt(&mut self, expression: &Expr) {
+        self.lint_expr(expression);
+    }
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) {
+        self.lint_expr(expression);
+    }
+    fn visit_var_stmt(
+        &mut self,
+        _name: &Token,
+        _type_annotation: &Option<TypeAnnotation>,
+        initializer: &Expr,
+    ) {
+        self.lint_expr(initializer);
+    }
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) {
+        self.lint_statements(statements);
+    }
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) {
+        self.lint_expr(condition);
+        then_branch.accept(self);
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self);
+        }
+    }
+    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) {
+        self.lint_expr(condition);
+        body.accept(self);
+    }
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        _param_types: &[Option<TypeAnnotation>],
+        _return_type: &Option<TypeAnnotation>,
+        body: &[Stmt],
+    ) {
+        if let Some(declaration) = self.local(name) {
+            self.arities
+                .insert(declaration, Arity::Exactly(params.len()));
+        }
+        self.lint_statements(body);
+    }
+    fn visit_return_stmt(&mut self, _keyword: &Token, value: &Expr) {
+        self.lint_expr(value);
+    }
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        super_class: &Option<Expr>,
+        traits: &[Expr],
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+        setters: &[Stmt],
+        _sealed: bool,
+    ) {
+        if let (Some(declaration), Some(arity)) =
+            (self.local(name), class_arity(super_class, traits, methods))
+        {
+            self.arities.insert(declaration, arity);
+        }
+        let variable_name = |expr: &Expr| match expr {
+            Expr::Variable { name } => name.lexeme.clone(),
+            _ => unreachable!(),
+        };
+        if let Some(super_class) = super_class {
+            self.lint_expr(super_class);
+        }
+        for used_trait in traits {
+            self.lint_expr(used_trait);
+        }
+        let info = ClassInfo {
+            super_class: super_class.as_ref().map(variable_name),
+            traits: traits.iter().map(variable_name).collect(),
+            members: names(methods)
+                .chain(names(class_methods))
+                .chain(names(getters))
+                .chain(names(setters))
+                .collect(),
+        };
+        self.classes.insert(name.lexeme.clone(), info);
+
+        self.current_class.push(Some(name.lexeme.clone()));
+        self.lint_methods(methods);
+        self.lint_methods(class_methods);
+        self.lint_methods(getters);
+        self.lint_methods(setters);
+        self.current_class.pop();
+    }
+    fn visit_trait_stmt(&mut self, name: &Token, methods: &[Stmt]) {
+        self.traits
+            .insert(name.lexeme.clone(), names(methods).collect());
+        self.current_class.push(None);
+        self.lint_methods(methods);
+        self.current_class.pop();
+    }
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) {
+        self.lint_expr(value);
+    }
+    fn visit_try_stmt(
+        &mut self,
+        try_branch: &[Stmt],
+        _catch_name: &Option<Token>,
+        catch_branch: &[Stmt],
+        finally_branch: &Option<Vec<Stmt>>,
+    ) {
+        self.lint_statements(try_branch);
+        self.lint_statements(catch_branch);
+        if let Some(finally_branch) = finally_branch {
+            self.lint_statements(finally_branch);
+        }
+    }
+}
@@ -3,26 +3,45 @@ use super::error::Result;
 use super::interpreter::Interpreter;
 use super::lox_instance::LoxInstance;
 use super::object::Object;
+use super::token::Token;
+use super::token_type::TokenType;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct LoxClass {
     pub name: String,
-    super_class: Option<Box<LoxClass>>,
+    super_class: Option<Rc<LoxClass>>,
     methods: HashMap<String, LoxFunction>,
+    class_methods: HashMap<String, LoxFunction>,
+    getters: HashMap<String, LoxFunction>,
+    setters: HashMap<String, LoxFunction>,
+    sealed: bool,
+    // Note: class-level fields, shared by every copy of the class
+    pub fields: Rc<RefCell<HashMap<String, Object>>>,
 }
 
 impl LoxClass {
     pub fn new(
         name: String,
-        super_class: Option<Box<LoxClass>>,
+        super_class: Option<Rc<LoxClass>>,
         methods: HashMap<String, LoxFunction>,
+        class_methods: HashMap<String, LoxFunction>,
+        getters: HashMap<String, LoxFunction>,
+        setters: HashMap<String, LoxFunction>,
+        sealed: bool,
     ) -> LoxClass {
         LoxClass {
             name,
             super_class,
             methods,
+            class_methods,
+            getters,
+            setters,
+            sealed,
+            fields: Rc::new(RefCell::new(HashMap::new())),
         }
     }
     pub fn find_method(&self, name: String) -> Option<&LoxFunction> {
@@ -35,14 +54,121 @@ impl LoxClass {
         }
         None
     }
+    // Note: static methods are inherited like instance methods
+    pub fn find_class_method(&self, name: String) -> Option<&LoxFunction> {
+        if let Some(m) = self.class_methods.get(&name) {
+            return Some(m);
+        }
+        match &self.super_class {
+            Some(super_class) => super_class.find_class_method(name),
+            None => None,
+        }
+    }
+    pub fn find_getter(&self, name: &str) -> Option<&LoxFunction> {
+        if let Some(getter) = self.getters.get(name) {
+            return Some(getter);
+        }
+        match &self.super_class {
+            Some(super_class) => super_class.find_getter(name),
+            None => None,
+        }
+    }
+    pub fn find_setter(&self, name: &str) -> Option<&LoxFunction> {
+        if let Some(setter) = self.setters.get(name) {
+            return Some(setter);
+        }
+        match &self.super_class {
+            Some(super_class) => super_class.find_setter(name),
+            None => None,
+        }
+    }
+    // Note: class-level fields are looked up through the superclass chain as well
+    pub fn find_field(&self, name: &str) -> Option<Object> {
+        if let Some(value) = self.fields.borrow().get(name) {
+            return Some(value.clone());
+        }
+        match &self.super_class {
+            Some(super_class) => super_class.find_field(name),
+            None => None,
+        }
+    }
+    // Note: the method a class defines to overload an operator applied with `operands`
+    // arguments besides the instance itself, e.g. `__add` for `a + b`
+    pub fn operator_method_name(operator: TokenType, operands: usize) -> Option<&'static str> {
+        let name = match (operator, operands) {
+            (TokenType::Minus, 0) => "__neg",
+            (TokenType::Tilde, 0) => "__invert",
+            (TokenType::Plus, 1) => "__add",
+            (TokenType::Minus, 1) => "__sub",
+            (TokenType::Star, 1) => "__mul",
+            (TokenType::Slash, 1) => "__div",
+            (TokenType::TildeSlash, 1) => "__floordiv",
+            (TokenType::Percent, 1) => "__mod",
+            (TokenType::StarStar, 1) => "__pow",
+            (TokenType::Less, 1) => "__lt",
+            (TokenType::LessEqual, 1) => "__le",
+            (TokenType::Greater, 1) => "__gt",
+            (TokenType::GreaterEqual, 1) => "__ge",
+            (TokenType::EqualEqual, 1) => "__eq",
+            (TokenType::BangEqual, 1) => "__ne",
+            (TokenType::Ampersand, 1) => "__band",
+            (TokenType::Pipe, 1) => "__bor",
+            (TokenType::Caret, 1) => "__bxor",
+            (TokenType::LessLess, 1) => "__shl",
+            (TokenType::GreaterGreater, 1) => "__shr",
+            (TokenType::LeftBracket, 1) => "__index",
+            (TokenType::LeftBracket, 2) => "__setindex",
+            _ => return None,
+        };
+        Some(name)
+    }
+    pub fn super_class(&self) -> Option<&Rc<LoxClass>> {
+        self.super_class.as_ref()
+    }
+    // Note: a subclass of a sealed class is sealed as well
+    pub fn is_sealed(&self) -> bool {
+        match &self.super_class {
+            Some(super_class) => self.sealed || super_class.is_sealed(),
+            None => self.sealed,
+        }
+    }
+    // Note: classes are compared by identity, two classes with the same name are different
+    pub fn inherits_from(&self, other: &LoxClass) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        match &self.super_class {
+            Some(super_class) => super_class.inherits_from(other),
+            None => false,
+        }
+    }
+    // Note: includes inherited methods, sorted and without duplicates from overrides
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.methods.keys().cloned().collect();
+        if let Some(super_class) = &self.super_class {
+            names.extend(super_class.method_names());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
 }
-impl LoxCallable for LoxClass {
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object> {
-        let instance = LoxInstance::new(self.clone());
+// Note: implemented on the Rc so instances share their class instead of copying it
+impl LoxCallable for Rc<LoxClass> {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        paren: &Token,
+        arguments: Vec<Object>,
+    ) -> Result<Object> {
+        let instance = LoxInstance::new(Rc::clone(self));
         if let Some(initializer) = self.find_method("init".to_string()) {
             initializer
                 .bind(instance.clone())
-                .call(interpreter, arguments)?;
+                .call(interpreter, paren, arguments)?;
+        }
+        if self.is_sealed() {
+            instance.seal();
         }
         Ok(Object::Instance(instance))
     }
@@ -66,4 +192,5 @@ pub enum ClassType {
     None,
     Class,
     SubClass,
+    Trait,
 }
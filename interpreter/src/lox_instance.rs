@@ -2,7 +2,7 @@ use super::error::{Error, Result};
 use super::lox_class::LoxClass;
 use super::object::Object;
 use super::token::Token;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
@@ -11,15 +11,20 @@ use std::rc::Rc;
 pub struct LoxInstance {
     class: Rc<LoxClass>,
     pub fields: Rc<RefCell<HashMap<String, Object>>>,
+    sealed: Rc<Cell<bool>>,
 }
 
 impl LoxInstance {
-    pub fn new(class: LoxClass) -> LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> LoxInstance {
         LoxInstance {
-            class: Rc::new(class),
+            class,
             fields: Rc::new(RefCell::new(HashMap::new())),
+            sealed: Rc::new(Cell::new(false)),
         }
     }
+    pub fn class(&self) -> &Rc<LoxClass> {
+        &self.class
+    }
     pub fn get(&mut self, name: &Token) -> Result<Object> {
         if let Some(o) = self.fields.borrow().get(&name.lexeme) {
             return Ok(o.clone());
@@ -32,19 +37,28 @@ impl LoxInstance {
             format!("Undefined property '{}'", name.lexeme),
         ))
     }
-    pub fn set(&mut self, name: &Token, value: &Object) {
-        self.fields
-            .borrow_mut()
-            .insert(name.lexeme.clone(), value.clone());
+    pub fn set(&mut self, name: &Token, value: &Object) -> Result<()> {
+        let mut fields = self.fields.borrow_mut();
+        if self.sealed.get() && !fields.contains_key(&name.lexeme) {
+            return Err(Error::RuntimeError(
+                name.clone(),
+                format!(
+                    "Cannot add field '{}' to an instance of sealed class '{}'.",
+                    name.lexeme, self.class.name
+                ),
+            ));
+        }
+        fields.insert(name.lexeme.clone(), value.clone());
+        Ok(())
+    }
+    // Note: called once the initializer has returned, fields must then already exist
+    pub fn seal(&self) {
+        self.sealed.set(true);
     }
 }
 
 impl fmt::Display for LoxInstance {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{} instance, fields has {:?}",
-            self.class.name, self.fields
-        )
+        write!(f, "<{} instance>", self.class.name)
     }
 }
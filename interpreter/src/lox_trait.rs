@@ -0,0 +1,22 @@
+use super::callable::LoxFunction;
+use std::collections::HashMap;
+use std::fmt;
+
+// Note: a named set of methods which classes copy in with `with`
+#[derive(Debug, Clone)]
+pub struct LoxTrait {
+    pub name: String,
+    pub methods: HashMap<String, LoxFunction>,
+}
+
+impl LoxTrait {
+    pub fn new(name: String, methods: HashMap<String, LoxFunction>) -> LoxTrait {
+        LoxTrait { name, methods }
+    }
+}
+
+impl fmt::Display for LoxTrait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<trait {}>", self.name)
+    }
+}
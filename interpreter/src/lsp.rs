@@ -0,0 +1,366 @@
+use super::token::Token;
+use analysis::{Analysis, DeclarationKind, Severity, Span, KEYWORDS};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+
+mod analysis;
+
+// JSON-RPC error codes from the specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Note: far above any source file, a bad Content-Length must not allocate gigabytes
+const MAX_MESSAGE_LENGTH: usize = 64 * 1024 * 1024;
+
+// Serves one client over stdin and stdout until it sends `exit`, and returns the exit status
+// the protocol asks for.
+pub fn run() -> i32 {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut server = Server::new(stdout.lock());
+    let mut input = stdin.lock();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return 1,
+            Err(e) => {
+                if server
+                    .reply_error(Value::Null, PARSE_ERROR, &e.to_string())
+                    .is_err()
+                {
+                    return 1;
+                }
+                continue;
+            }
+        };
+        match server.handle(&message) {
+            Ok(Some(code)) => return code,
+            Ok(None) => {}
+            Err(_) => return 1,
+        }
+    }
+}
+
+// Note: messages are framed by a Content-Length header, other headers are ignored
+pub(crate) fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    let length = loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            match length {
+                Some(length) => break length,
+                None => continue,
+            }
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    };
+    // Note: the body is skipped without being stored, so the next message is still found
+    if length > MAX_MESSAGE_LENGTH {
+        io::copy(&mut input.by_ref().take(length as u64), &mut io::sink())?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Message of {} bytes is larger than the limit of {} bytes.",
+                length, MAX_MESSAGE_LENGTH
+            ),
+        ));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub struct Server<W: Write> {
+    output: W,
+    documents: HashMap<String, Analysis>,
+    shutdown: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(output: W) -> Server<W> {
+        Server {
+            output,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    // Returns the exit status once the client sends `exit`.
+    pub fn handle(&mut self, message: &Value) -> io::Result<Option<i32>> {
+        let id = message.get("id").cloned();
+        let method = match message.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            // Note: a response to a request of ours, the server never sends any
+            None if id.is_some() && message.get("method").is_none() => return Ok(None),
+            None => {
+                self.reply_error(Value::Null, INVALID_REQUEST, "Missing method.")?;
+                return Ok(None);
+            }
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let id = match id {
+            Some(id) => id,
+            None => {
+                return self.notification(method, &params);
+            }
+        };
+        if self.shutdown && method != "exit" {
+            return self
+                .reply_error(id, INVALID_REQUEST, "The server is shutting down.")
+                .map(|_| None);
+        }
+        let result = match method {
+            "initialize" => Some(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Value::Null)
+            }
+            "textDocument/definition" => {
+                self.with_position(&params, |analysis, uri, line, character| {
+                    match analysis.definition(line, character) {
+                        Some(token) => location(uri, token),
+                        None => Value::Null,
+                    }
+                })
+            }
+            "textDocument/references" => {
+                let include_declaration = params["context"]["includeDeclaration"]
+                    .as_bool()
+                    .unwrap_or(true);
+                self.with_position(&params, |analysis, uri, line, character| {
+                    Value::Array(
+                        analysis
+                            .references(line, character, include_declaration)
+                            .into_iter()
+                            .map(|token| location(uri, token))
+                            .collect(),
+                    )
+                })
+            }
+            "textDocument/hover" => self.with_position(&params, |analysis, _, line, character| {
+                match analysis.hover(line, character) {
+                    Some(hover) => json!({
+                        "contents": { "kind": "markdown", "value": hover },
+                        "range": range(Span::of(analysis.token_at(line, character).unwrap())),
+                    }),
+                    None => Value::Null,
+                }
+            }),
+            "textDocument/documentSymbol" => self
+                .document(&params)
+                .map(|(analysis, _)| document_symbols(analysis)),
+            "textDocument/completion" => self
+                .document(&params)
+                .map(|(analysis, _)| completions(analysis)),
+            _ => {
+                let message = format!("Unknown method {}.", method);
+                return self
+                    .reply_error(id, METHOD_NOT_FOUND, &message)
+                    .map(|_| None);
+            }
+        };
+        match result {
+            Some(result) => self.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }))?,
+            None => self.reply_error(id, INVALID_PARAMS, "Unknown document or position.")?,
+        }
+        Ok(None)
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<Option<i32>> {
+        match method {
+            "exit" => return Ok(Some(if self.shutdown { 0 } else { 1 })),
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                if let (Some(uri), Some(text)) =
+                    (document["uri"].as_str(), document["text"].as_str())
+                {
+                    self.update(uri, text)?;
+                }
+            }
+            "textDocument/didChange" => {
+                // Note: the server asks for full syncs, so the last change holds the whole text
+                let uri = params["textDocument"]["uri"].as_str();
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.update(uri, text)?;
+                }
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.documents.remove(uri);
+                    self.send(json!({
+                        "jsonrpc": "2.0",
+                        "method": "textDocument/publishDiagnostics",
+                        "params": { "uri": uri, "diagnostics": [] },
+                    }))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, uri: &str, text: &str) -> io::Result<()> {
+        let analysis = Analysis::new(text);
+        let diagnostics: Vec<Value> = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let mut value = json!({
+                    "range": range(diagnostic.span),
+                    "severity": match diagnostic.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "source": "lox",
+                    "message": diagnostic.message,
+                });
+                if let Some(code) = diagnostic.code {
+                    value["code"] = json!(code);
+                }
+                value
+            })
+            .collect();
+        self.documents.insert(String::from(uri), analysis);
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }))
+    }
+
+    fn document<'a>(&'a self, params: &'a Value) -> Option<(&'a Analysis, &'a str)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let analysis = self.documents.get(uri)?;
+        Some((analysis, uri))
+    }
+
+    fn with_position<F>(&self, params: &Value, f: F) -> Option<Value>
+    where
+        F: Fn(&Analysis, &str, usize, usize) -> Value,
+    {
+        let (analysis, uri) = self.document(params)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        Some(f(analysis, uri, line, character))
+    }
+
+    fn reply_error(&mut self, id: Value, code: i64, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }))
+    }
+
+    fn send(&mut self, message: Value) -> io::Result<()> {
+        let body = message.to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": 1,
+            "definitionProvider": true,
+            "referencesProvider": true,
+            "hoverProvider": true,
+            "documentSymbolProvider": true,
+            "completionProvider": {},
+        },
+        "serverInfo": { "name": "lox-rust", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn range(span: Span) -> Value {
+    json!({
+        "start": { "line": span.line, "character": span.start },
+        "end": { "line": span.line, "character": span.end },
+    })
+}
+
+fn location(uri: &str, token: &Token) -> Value {
+    json!({ "uri": uri, "range": range(Span::of(token)) })
+}
+
+// Note: classes and traits list their members, locals of functions and blocks are left out
+fn document_symbols(analysis: &Analysis) -> Value {
+    let symbol = |index: usize| {
+        let declaration = &analysis.declarations[index];
+        let kind = match declaration.kind {
+            DeclarationKind::Class => 5,
+            DeclarationKind::Method => 6,
+            DeclarationKind::Property => 7,
+            DeclarationKind::Trait => 11,
+            DeclarationKind::Function => 12,
+            DeclarationKind::Variable | DeclarationKind::Parameter => 13,
+        };
+        json!({
+            "name": declaration.name.lexeme,
+            "detail": declaration.signature,
+            "kind": kind,
+            "range": range(Span::of(&declaration.name)),
+            "selectionRange": range(Span::of(&declaration.name)),
+        })
+    };
+    let mut symbols = vec![];
+    for (index, declaration) in analysis.declarations.iter().enumerate() {
+        if !declaration.global {
+            continue;
+        }
+        let mut value = symbol(index);
+        if matches!(
+            declaration.kind,
+            DeclarationKind::Class | DeclarationKind::Trait
+        ) {
+            let children: Vec<Value> = analysis
+                .declarations
+                .iter()
+                .enumerate()
+                .filter(|(_, member)| member.parent == Some(index))
+                .map(|(member, _)| symbol(member))
+                .collect();
+            value["children"] = Value::Array(children);
+        }
+        symbols.push(value);
+    }
+    Value::Array(symbols)
+}
+
+fn completions(analysis: &Analysis) -> Value {
+    let mut items: Vec<Value> = KEYWORDS
+        .iter()
+        .map(|keyword| json!({ "label": keyword, "kind": 14 }))
+        .collect();
+    for (name, kind) in analysis.completions() {
+        let kind = match kind {
+            DeclarationKind::Function => 3,
+            DeclarationKind::Class => 7,
+            DeclarationKind::Trait => 8,
+            _ => 6,
+        };
+        items.push(json!({ "label": name, "kind": kind }));
+    }
+    Value::Array(items)
+}
@@ -0,0 +1,585 @@
+use crate::callable::LoxCallable;
+use crate::environment::Environment;
+use crate::error::Error;
+use crate::expr::Expr;
+use crate::interpreter::Interpreter;
+use crate::lexer::Lexer;
+use crate::lint::Linter;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::resolver::{Resolver, Symbols};
+use crate::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use crate::type_checker::TypeChecker;
+use crate::types::TypeAnnotation;
+use std::collections::HashMap;
+
+pub const KEYWORDS: [&str; 23] = [
+    "and", "catch", "class", "else", "false", "finally", "for", "fun", "if", "is", "nil", "or",
+    "print", "return", "super", "this", "throw", "trait", "true", "try", "var", "while", "with",
+];
+
+// Note: zero-based like the protocol, and never spans more than one line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn of(token: &Token) -> Span {
+        let start = token.column - 1;
+        Span {
+            line: token.line - 1,
+            start,
+            end: start + token.lexeme.chars().count(),
+        }
+    }
+
+    fn contains(&self, line: usize, character: usize) -> bool {
+        self.line == line && self.start <= character && character <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    // Note: the lint ID for warnings
+    pub code: Option<&'static str>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Function,
+    Method,
+    Property,
+    Class,
+    Trait,
+    Variable,
+    Parameter,
+}
+
+#[derive(Debug)]
+pub struct Declaration {
+    pub name: Token,
+    pub kind: DeclarationKind,
+    // Note: the declaration as written, like `fun add(a, b)`
+    pub signature: String,
+    pub arity: Option<usize>,
+    // Note: index of the enclosing function, class or trait
+    pub parent: Option<usize>,
+    // Note: false inside blocks and functions, where the name is a local
+    pub global: bool,
+}
+
+// Everything the language server knows about one version of a document. Only the
+// diagnostics are available when the document does not parse.
+#[derive(Debug)]
+pub struct Analysis {
+    tokens: Vec<Token>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub declarations: Vec<Declaration>,
+    symbols: Symbols,
+    // Note: how many blocks enclose the statement being declared
+    block_depth: usize,
+    // Note: natives and prelude globals with their arity
+    globals: HashMap<String, Option<usize>>,
+}
+
+impl Analysis {
+    pub fn new(source: &str) -> Analysis {
+        let mut lexer = Lexer::new(String::from(source)).preserving_comments();
+        let tokens = lexer.tokenize_all();
+        let lines: Vec<&str> = source.lines().collect();
+        let mut diagnostics: Vec<Diagnostic> = lexer
+            .errors
+            .iter()
+            .map(|(line, message)| Diagnostic {
+                span: line_span(&lines, *line),
+                severity: Severity::Error,
+                code: None,
+                message: message.clone(),
+            })
+            .collect();
+
+        let mut interpreter = Interpreter::new(Environment::new(None, false));
+        let globals = interpreter
+            .globals
+            .borrow()
+            .values
+            .borrow()
+            .iter()
+            .map(|(name, value)| {
+                let arity = match value {
+                    Object::Native(function) => Some(function.arity()),
+                    Object::Func(function) => Some(function.arity()),
+                    Object::Class(class) => Some(class.arity()),
+                    _ => None,
+                };
+                (name.clone(), arity)
+            })
+            .collect();
+        let mut analysis = Analysis {
+            tokens,
+            diagnostics: vec![],
+            declarations: vec![],
+            symbols: Symbols::default(),
+            block_depth: 0,
+            globals,
+        };
+
+        let code: Vec<Token> = analysis
+            .tokens
+            .iter()
+            .filter(|token| token.token_type != TokenType::Comment)
+            .cloned()
+            .collect();
+        let statements = match Parser::new(code).parse() {
+            Ok(statements) => statements,
+            Err(e) => {
+                diagnostics.push(Diagnostic {
+                    span: Span::of(&e.1),
+                    severity: Severity::Error,
+                    code: None,
+                    message: e.0,
+                });
+                analysis.diagnostics = diagnostics;
+                return analysis;
+            }
+        };
+        analysis.declare_all(&statements, None);
+
+        let mut resolver = Resolver::new(&mut interpreter);
+        let resolved = resolver.resolve_statements(&statements);
+        analysis.symbols = resolver.into_symbols();
+        match resolved {
+            Ok(()) => {
+                let linter = Linter::new(&interpreter.globals.borrow());
+                for warning in linter.lint(&statements, &analysis.symbols, source) {
+                    diagnostics.push(Diagnostic {
+                        span: line_span(&lines, warning.line),
+                        severity: Severity::Warning,
+                        code: Some(warning.id),
+                        message: warning.message,
+                    });
+                }
+                let checker = TypeChecker::new(&interpreter.globals.borrow());
+                for type_error in checker.check(&statements) {
+                    let span = analysis
+                        .token_at(type_error.line - 1, type_error.column - 1)
+                        .map(Span::of)
+                        .unwrap_or_else(|| line_span(&lines, type_error.line));
+                    diagnostics.push(Diagnostic {
+                        span,
+                        severity: Severity::Error,
+                        code: None,
+                        message: type_error.message,
+                    });
+                }
+            }
+            Err(Error::ResolveError(token, message)) | Err(Error::RuntimeError(token, message)) => {
+                diagnostics.push(Diagnostic {
+                    span: Span::of(&token),
+                    severity: Severity::Error,
+                    code: None,
+                    message,
+                })
+            }
+            Err(e) => diagnostics.push(Diagnostic {
+                span: line_span(&lines, 1),
+                severity: Severity::Error,
+                code: None,
+                message: e.to_string(),
+            }),
+        }
+        analysis.diagnostics = diagnostics;
+        analysis
+    }
+
+    // Note: only names, an identifier is found with the cursor anywhere on it or right after it
+    pub fn token_at(&self, line: usize, character: usize) -> Option<&Token> {
+        self.tokens.iter().find(|token| {
+            matches!(
+                token.token_type,
+                TokenType::Identifier | TokenType::PrivateIdentifier
+            ) && Span::of(token).contains(line, character)
+        })
+    }
+
+    fn declaration_index(&self, token: &Token) -> Option<usize> {
+        let span = Span::of(token);
+        if let Some(index) = self
+            .symbols
+            .declarations
+            .iter()
+            .position(|declaration| Span::of(declaration) == span)
+        {
+            return Some(index);
+        }
+        self.symbols
+            .references
+            .iter()
+            .find(|(reference, _)| Span::of(reference) == span)
+            .map(|(_, index)| *index)
+    }
+
+    pub fn definition(&self, line: usize, character: usize) -> Option<&Token> {
+        let token = self.token_at(line, character)?;
+        let index = self.declaration_index(token)?;
+        Some(&self.symbols.declarations[index])
+    }
+
+    pub fn references(
+        &self,
+        line: usize,
+        character: usize,
+        include_declaration: bool,
+    ) -> Vec<&Token> {
+        let index = match self
+            .token_at(line, character)
+            .and_then(|token| self.declaration_index(token))
+        {
+            Some(index) => index,
+            None => return vec![],
+        };
+        let mut references = vec![];
+        if include_declaration {
+            references.push(&self.symbols.declarations[index]);
+        }
+        for (reference, declaration) in &self.symbols.references {
+            if *declaration == index {
+                references.push(reference);
+            }
+        }
+        references
+    }
+
+    // Returns markdown with the declaration, its arity and the comment lines right above it.
+    pub fn hover(&self, line: usize, character: usize) -> Option<String> {
+        let token = self.token_at(line, character)?;
+        let name = match self.definition(line, character) {
+            Some(name) => name,
+            None => {
+                let arity = self.globals.get(&token.lexeme)?;
+                return Some(match arity {
+                    Some(arity) => format!(
+                        "```lox\nfun {}\n```\n\nNative function, {}.",
+                        token.lexeme,
+                        takes(*arity)
+                    ),
+                    None => format!("```lox\nvar {}\n```\n\nNative value.", token.lexeme),
+                });
+            }
+        };
+        let span = Span::of(name);
+        let declaration = self
+            .declarations
+            .iter()
+            .find(|declaration| Span::of(&declaration.name) == span)?;
+        let mut hover = format!("```lox\n{}\n```", declaration.signature);
+        if let Some(arity) = declaration.arity {
+            hover.push_str(&format!("\n\n{}.", capitalize(&takes(arity))));
+        }
+        let doc = match declaration.kind {
+            DeclarationKind::Parameter => String::new(),
+            _ => self.doc_comment(name),
+        };
+        if !doc.is_empty() {
+            hover.push_str("\n\n");
+            hover.push_str(&doc);
+        }
+        Some(hover)
+    }
+
+    fn doc_comment(&self, name: &Token) -> String {
+        let first = match self.tokens.iter().position(|token| token.line == name.line) {
+            Some(first) => first,
+            None => return String::new(),
+        };
+        let mut lines = vec![];
+        let mut expected_line = name.line;
+        for token in self.tokens[..first].iter().rev() {
+            if token.token_type != TokenType::Comment || token.line + 1 != expected_line {
+                break;
+            }
+            let text = token.lexeme.trim_start_matches('/');
+            lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+            expected_line = token.line;
+        }
+        lines.reverse();
+        lines.join("\n")
+    }
+
+    // Returns the names worth offering at any position with their kind.
+    pub fn completions(&self) -> Vec<(String, DeclarationKind)> {
+        let mut names: HashMap<String, DeclarationKind> = HashMap::new();
+        for (name, arity) in &self.globals {
+            let kind = match arity {
+                Some(_) => DeclarationKind::Function,
+                None => DeclarationKind::Variable,
+            };
+            names.insert(name.clone(), kind);
+        }
+        for declaration in &self.declarations {
+            if declaration.kind != DeclarationKind::Method
+                && declaration.kind != DeclarationKind::Property
+            {
+                names.insert(declaration.name.lexeme.clone(), declaration.kind);
+            }
+        }
+        let mut names: Vec<(String, DeclarationKind)> = names.into_iter().collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        names
+    }
+
+    fn declare(
+        &mut self,
+        name: &Token,
+        kind: DeclarationKind,
+        signature: String,
+        arity: Option<usize>,
+        parent: Option<usize>,
+    ) -> usize {
+        self.declarations.push(Declaration {
+            name: name.clone(),
+            kind,
+            signature,
+            arity,
+            parent,
+            global: parent.is_none() && self.block_depth == 0,
+        });
+        self.declarations.len() - 1
+    }
+
+    fn declare_all(&mut self, statements: &[Stmt], parent: Option<usize>) {
+        for statement in statements {
+            self.declare_statement(statement, parent);
+        }
+    }
+
+    fn declare_statement(&mut self, statement: &Stmt, parent: Option<usize>) {
+        match statement {
+            Stmt::Var {
+                name,
+                type_annotation,
+                ..
+            } => {
+                let signature = format!("var {}{}", name.lexeme, annotation(type_annotation));
+                self.declare(name, DeclarationKind::Variable, signature, None, parent);
+            }
+            Stmt::Function { .. } => {
+                self.declare_function(statement, DeclarationKind::Function, "fun ", parent);
+            }
+            Stmt::Class {
+                name,
+                super_class,
+                traits,
+                methods,
+                class_methods,
+                getters,
+                setters,
+                sealed,
+            } => {
+                let mut signature = format!(
+                    "{}class {}",
+                    if *sealed { "sealed " } else { "" },
+                    name.lexeme
+                );
+                if let Some(Expr::Variable { name }) = super_class {
+                    signature.push_str(&format!(" < {}", name.lexeme));
+                }
+                let trait_names: Vec<&str> = traits
+                    .iter()
+                    .filter_map(|used_trait| match used_trait {
+                        Expr::Variable { name } => Some(name.lexeme.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                if !trait_names.is_empty() {
+                    signature.push_str(&format!(" with {}", trait_names.join(", ")));
+                }
+                let arity = methods.iter().find_map(|method| match method {
+                    Stmt::Function { name, params, .. } if name.lexeme == "init" => {
+                        Some(params.len())
+                    }
+                    _ => None,
+                });
+                let class = self.declare(
+                    name,
+                    DeclarationKind::Class,
+                    signature,
+                    Some(arity.unwrap_or(0)),
+                    parent,
+                );
+                let prefix = format!("{}.", name.lexeme);
+                for method in methods {
+                    self.declare_function(method, DeclarationKind::Method, &prefix, Some(class));
+                }
+                let class_prefix = format!("class {}.", name.lexeme);
+                for method in class_methods {
+                    self.declare_function(
+                        method,
+                        DeclarationKind::Method,
+                        &class_prefix,
+                        Some(class),
+                    );
+                }
+                for getter in getters {
+                    self.declare_function(getter, DeclarationKind::Property, &prefix, Some(class));
+                }
+                for setter in setters {
+                    self.declare_function(setter, DeclarationKind::Property, &prefix, Some(class));
+                }
+            }
+            Stmt::Trait { name, methods } => {
+                let signature = format!("trait {}", name.lexeme);
+                let declared = self.declare(name, DeclarationKind::Trait, signature, None, parent);
+                let prefix = format!("{}.", name.lexeme);
+                for method in methods {
+                    self.declare_function(method, DeclarationKind::Method, &prefix, Some(declared));
+                }
+            }
+            Stmt::Block { statements } => {
+                self.block_depth += 1;
+                self.declare_all(statements, parent);
+                self.block_depth -= 1;
+            }
+            Stmt::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.declare_statement(then_branch, parent);
+                if let Some(else_branch) = else_branch {
+                    self.declare_statement(else_branch, parent);
+                }
+            }
+            Stmt::While { body, .. } => self.declare_statement(body, parent),
+            Stmt::Try {
+                try_branch,
+                catch_name,
+                catch_branch,
+                finally_branch,
+            } => {
+                self.block_depth += 1;
+                self.declare_all(try_branch, parent);
+                if let Some(catch_name) = catch_name {
+                    let signature = format!("catch ({})", catch_name.lexeme);
+                    self.declare(
+                        catch_name,
+                        DeclarationKind::Variable,
+                        signature,
+                        None,
+                        parent,
+                    );
+                }
+                self.declare_all(catch_branch, parent);
+                if let Some(finally_branch) = finally_branch {
+                    self.declare_all(finally_branch, parent);
+                }
+                self.block_depth -= 1;
+            }
+            Stmt::Expression { .. }
+            | Stmt::Print { .. }
+            | Stmt::Return { .. }
+            | Stmt::Throw { .. } => {}
+        }
+    }
+
+    fn declare_function(
+        &mut self,
+        function: &Stmt,
+        kind: DeclarationKind,
+        prefix: &str,
+        parent: Option<usize>,
+    ) {
+        let (name, params, param_types, return_type, body) = match function {
+            Stmt::Function {
+                name,
+                params,
+                param_types,
+                return_type,
+                body,
+            } => (name, params, param_types, return_type, body),
+            _ => return,
+        };
+        let params_signature: Vec<String> = params
+            .iter()
+            .zip(param_types.iter())
+            .map(|(param, param_type)| format!("{}{}", param.lexeme, annotation(param_type)))
+            .collect();
+        let getter = kind == DeclarationKind::Property && params.is_empty();
+        let signature = if getter {
+            format!("{}{}{}", prefix, name.lexeme, annotation(return_type))
+        } else {
+            format!(
+                "{}{}({}){}",
+                prefix,
+                name.lexeme,
+                params_signature.join(", "),
+                annotation(return_type)
+            )
+        };
+        let arity = if getter { None } else { Some(params.len()) };
+        let function = self.declare(name, kind, signature, arity, parent);
+        for (param, param_type) in params.iter().zip(param_types.iter()) {
+            let signature = format!(
+                "{}{} (parameter of {})",
+                param.lexeme,
+                annotation(param_type),
+                name.lexeme
+            );
+            self.declare(
+                param,
+                DeclarationKind::Parameter,
+                signature,
+                None,
+                Some(function),
+            );
+        }
+        self.declare_all(body, Some(function));
+    }
+}
+
+fn annotation(type_annotation: &Option<TypeAnnotation>) -> String {
+    match type_annotation {
+        Some(type_annotation) => format!(": {}", type_annotation),
+        None => String::new(),
+    }
+}
+
+fn takes(arity: usize) -> String {
+    match arity {
+        1 => String::from("takes 1 argument"),
+        n => format!("takes {} arguments", n),
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Note: lexer errors and lint warnings only know their line, they cover it without the indentation
+fn line_span(lines: &[&str], line: usize) -> Span {
+    let text = lines.get(line.saturating_sub(1)).unwrap_or(&"");
+    let indentation = text.chars().take_while(|c| c.is_whitespace()).count();
+    Span {
+        line: line.saturating_sub(1),
+        start: indentation,
+        end: text.trim_end().chars().count().max(indentation),
+    }
+}
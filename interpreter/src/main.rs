@@ -1,43 +1,45 @@
-// use ast_printer::AstPrinter;
-use clap::{App, Arg};
-use environment::Environment;
-use error::{Error, Result};
-use interpreter::Interpreter;
+// use lox::ast_printer::AstPrinter;
+use clap::{App, AppSettings, Arg};
 use log::{debug, error, info};
-use parser::Parser;
-use resolver::Resolver;
+use lox::debugger::console::Console;
+use lox::debugger::{self, Debugger};
+use lox::environment::Environment;
+use lox::error::{Error, Result};
+use lox::interpreter::Interpreter;
+use lox::lint::{Linter, Warning};
+use lox::parser::Parser;
+use lox::profiler::Profiler;
+use lox::resolver::{Resolver, Symbols};
+use lox::stmt::Stmt;
+use lox::token::Token;
+use lox::type_checker::{TypeChecker, TypeError};
+use lox::{formatter, lexer, native};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{self, BufReader};
 use std::process::exit;
-
-// mod ast_printer;
-mod callable;
-mod environment;
-mod error;
-mod expr;
-mod interpreter;
-mod lexer;
-mod lox_class;
-mod lox_instance;
-mod object;
-mod parser;
-mod resolver;
-mod stmt;
-mod token;
-mod token_type;
+use std::rc::Rc;
 
 fn main() -> io::Result<()> {
     let matches = App::new("lox-rust")
         .version("0.1")
         .author("Daiki Ihara <sasurau4@gmail.com>")
         .about("lox rust")
+        .setting(AppSettings::TrailingVarArg)
         .arg(
             Arg::with_name("input")
                 .about("the input file to run")
                 .index(1)
                 .required(false),
         )
+        .arg(
+            Arg::with_name("args")
+                .about("arguments passed to the script as `args`")
+                .index(2)
+                .multiple(true),
+        )
         .arg(
             Arg::new("debug")
                 .long("debug")
@@ -46,6 +48,118 @@ fn main() -> io::Result<()> {
                 .takes_value(false)
                 .about("Turn debugging information on"),
         )
+        .arg(
+            Arg::with_name("no-io")
+                .long("no-io")
+                .takes_value(false)
+                .about("Disable the file and stdin natives"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .takes_value(true)
+                .about("Seed the random number generator for reproducible runs"),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .takes_value(false)
+                .about("Print the time spent in every function and the hottest lines to stderr"),
+        )
+        .arg(
+            Arg::with_name("profile-stacks")
+                .long("profile-stacks")
+                .takes_value(true)
+                .about("Profile the script and write its collapsed stacks to this file"),
+        )
+        .subcommand(
+            App::new("run")
+                .about("Run a script, also one named like a subcommand")
+                .setting(AppSettings::TrailingVarArg)
+                .arg(
+                    Arg::with_name("input")
+                        .about("the input file to run")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .about("arguments passed to the script as `args`")
+                        .index(2)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            App::new("lint")
+                .about("Report likely mistakes without running the script")
+                .arg(
+                    Arg::with_name("input")
+                        .about("the input file to lint")
+                        .index(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("check")
+                .about("Check the type annotations without running the script")
+                .arg(
+                    Arg::with_name("input")
+                        .about("the input file to check")
+                        .index(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("fmt")
+                .about("Rewrite the files in the standard style")
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .takes_value(false)
+                        .about("List the files which are not formatted instead of rewriting them"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .about("the input files to format")
+                        .index(1)
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("debug")
+                .about("Run a script under the debugger, paused at its first statement")
+                .setting(AppSettings::TrailingVarArg)
+                .arg(
+                    Arg::with_name("break")
+                        .long("break")
+                        .short('b')
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .about(
+                            "Run until this line instead of pausing at the start, can be repeated",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("dap")
+                        .long("dap")
+                        .takes_value(false)
+                        .about("Speak the Debug Adapter Protocol over stdin and stdout"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .about("the input file to debug")
+                        .index(1)
+                        .required_unless("dap"),
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .about("arguments passed to the script as `args`")
+                        .index(2)
+                        .multiple(true),
+                ),
+        )
         .get_matches();
 
     let log_level = match matches.occurrences_of("debug") {
@@ -56,49 +170,265 @@ fn main() -> io::Result<()> {
     env_logger::builder().filter_level(log_level).init();
     info!("log_level: {}", log_level);
 
-    if let Some(ref in_file) = matches.value_of("input") {
+    if let Some(lint_matches) = matches.subcommand_matches("lint") {
+        let in_file = lint_matches.value_of("input").unwrap();
+        return lint_file(in_file);
+    }
+    if let Some(check_matches) = matches.subcommand_matches("check") {
+        let in_file = check_matches.value_of("input").unwrap();
+        return check_file(in_file);
+    }
+    if let Some(fmt_matches) = matches.subcommand_matches("fmt") {
+        let in_files: Vec<&str> = fmt_matches.values_of("input").unwrap().collect();
+        return fmt_files(&in_files, fmt_matches.is_present("check"));
+    }
+    if let Some(debug_matches) = matches.subcommand_matches("debug") {
+        if debug_matches.is_present("dap") {
+            exit(debugger::dap::run());
+        }
+        let mut breakpoints = BTreeSet::new();
+        for line in debug_matches.values_of("break").into_iter().flatten() {
+            match line.parse::<usize>() {
+                Ok(line) => breakpoints.insert(line),
+                Err(_) => {
+                    error!("--break expects a line number but got {}.", line);
+                    exit(64);
+                }
+            };
+        }
+        let options = RunOptions {
+            seed: None,
+            allow_io: true,
+            args: match debug_matches.values_of("args") {
+                Some(values) => values.map(String::from).collect(),
+                None => Vec::new(),
+            },
+        };
+        let in_file = debug_matches.value_of("input").unwrap();
+        return debug_file(in_file, breakpoints, &options);
+    }
+
+    let seed = match matches.value_of("seed") {
+        Some(seed) => match seed.parse::<i64>() {
+            Ok(n) => Some(n as u64),
+            Err(_) => {
+                error!("--seed expects an integer but got {}.", seed);
+                exit(64);
+            }
+        },
+        None => None,
+    };
+
+    // Note: the options of `run` are given before it, like `--seed 1 run lint`
+    let script_matches = matches.subcommand_matches("run").unwrap_or(&matches);
+    let options = RunOptions {
+        seed,
+        allow_io: !matches.is_present("no-io"),
+        args: match script_matches.values_of("args") {
+            Some(values) => values.map(String::from).collect(),
+            None => Vec::new(),
+        },
+    };
+
+    let profile = matches.is_present("profile");
+    let stacks = matches.value_of("profile-stacks");
+    if let Some(ref in_file) = script_matches.value_of("input") {
+        if profile || stacks.is_some() {
+            return profile_file(in_file, &options, profile, stacks);
+        }
         debug!("run for {}", in_file);
-        run_file(in_file)?
+        run_file(in_file, &options)?
+    } else if profile || stacks.is_some() {
+        error!("--profile and --profile-stacks need a script to run.");
+        exit(64);
     } else {
-        run_prompt()?
+        run_prompt(&options)?
     }
 
     Ok(())
 }
 
-fn run_file(path: &str) -> io::Result<()> {
-    let f = File::open(path)?;
-    let f = BufReader::new(f);
-    let mut source = String::from("");
-    let env = Environment::new(None, false);
+struct RunOptions {
+    seed: Option<u64>,
+    allow_io: bool,
+    args: Vec<String>,
+}
 
+fn new_interpreter(is_repl: bool, options: &RunOptions) -> Interpreter {
+    let env = Environment::new(None, is_repl);
     let mut interpreter = Interpreter::new(env);
+    interpreter.allow_io = options.allow_io;
+    if let Some(seed) = options.seed {
+        interpreter.rng.seed(seed);
+    }
+    native::process::define_args(&interpreter.globals.borrow(), options.args.clone());
+    interpreter
+}
 
+fn read_source(path: &str) -> io::Result<String> {
+    let f = File::open(path)?;
+    let f = BufReader::new(f);
+    let mut source = String::from("");
     for line in f.lines() {
         source.push_str(&line.unwrap());
-        source.push_str("\n")
+        source.push('\n')
     }
-    if let Err(e) = run(&source, &mut interpreter) {
-        error!("{:?}", e);
+    Ok(source)
+}
+
+fn run_file(path: &str, options: &RunOptions) -> io::Result<()> {
+    let source = read_source(path)?;
+    let mut interpreter = new_interpreter(false, options);
+    match run(&source, &mut interpreter) {
+        Ok(()) => {}
+        Err(Error::Exit(code)) => exit(code),
+        Err(e) => {
+            error!("{}", e);
+            exit(70);
+        }
+    };
+
+    Ok(())
+}
+
+// Note: exits with 1 when there are warnings so scripts and CI can fail on them
+fn lint_file(path: &str) -> io::Result<()> {
+    let source = read_source(path)?;
+    let mut interpreter = Interpreter::new(Environment::new(None, false));
+    match lint(&source, &mut interpreter) {
+        Ok(warnings) => {
+            for warning in &warnings {
+                println!("{}: {}", path, warning);
+            }
+            if !warnings.is_empty() {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            error!("{}", e);
+            exit(70);
+        }
+    }
+    Ok(())
+}
+
+// Note: exits with 1 when there are type errors, like lint
+fn check_file(path: &str) -> io::Result<()> {
+    let source = read_source(path)?;
+    let mut interpreter = Interpreter::new(Environment::new(None, false));
+    match check(&source, &mut interpreter) {
+        Ok(errors) => {
+            for type_error in &errors {
+                println!("{}: {}", path, type_error);
+            }
+            if !errors.is_empty() {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            error!("{}", e);
+            exit(70);
+        }
+    }
+    Ok(())
+}
+
+// Note: with --check nothing is written and the exit status is 1 when a file would change
+fn fmt_files(paths: &[&str], check: bool) -> io::Result<()> {
+    let mut unformatted = false;
+    let mut failed = false;
+    for path in paths {
+        let source = read_source(path)?;
+        let formatted = match formatter::format(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                error!("{}: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", path);
+            unformatted = true;
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+    }
+    if failed {
         exit(70);
+    }
+    if unformatted {
+        exit(1);
+    }
+    Ok(())
+}
+
+// Note: pauses at the start unless breakpoints were given on the command line
+fn debug_file(path: &str, breakpoints: BTreeSet<usize>, options: &RunOptions) -> io::Result<()> {
+    let source = read_source(path)?;
+    let mut interpreter = new_interpreter(false, options);
+    let mut debugger = Debugger::new(Box::new(Console::new(&source)), breakpoints.is_empty());
+    debugger.breakpoints = breakpoints;
+    interpreter.hook = Some(Rc::new(RefCell::new(debugger)));
+    match run(&source, &mut interpreter) {
+        Ok(()) => println!("The program finished."),
+        Err(Error::Exit(code)) => exit(code),
+        Err(e) => {
+            error!("{}", e);
+            exit(70);
+        }
     };
+    Ok(())
+}
 
+// Note: the report goes to stderr so it does not mix with what the script prints
+fn profile_file(
+    path: &str,
+    options: &RunOptions,
+    report: bool,
+    stacks: Option<&str>,
+) -> io::Result<()> {
+    let source = read_source(path)?;
+    let mut interpreter = new_interpreter(false, options);
+    let profiler = Rc::new(RefCell::new(Profiler::new()));
+    interpreter.hook = Some(profiler.clone());
+    let result = run(&source, &mut interpreter);
+    let mut profiler = profiler.borrow_mut();
+    profiler.finish();
+    if report {
+        eprint!("{}", profiler.report(&source));
+    }
+    if let Some(stacks) = stacks {
+        std::fs::write(stacks, profiler.collapsed_stacks())?;
+    }
+    match result {
+        Ok(()) => {}
+        Err(Error::Exit(code)) => exit(code),
+        Err(e) => {
+            error!("{}", e);
+            exit(70);
+        }
+    };
     Ok(())
 }
 
-fn run_prompt() -> io::Result<()> {
+fn run_prompt(options: &RunOptions) -> io::Result<()> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     print!("> ");
     stdout.flush().unwrap();
-    let env = Environment::new(None, true);
-    let mut interpreter = Interpreter::new(env);
+    let mut interpreter = new_interpreter(true, options);
     let mut source = String::from("");
 
     for line in stdin.lock().lines() {
         source.push_str(&line.unwrap());
-        if let Err(e) = run(&source, &mut interpreter) {
-            error!("{:?}", e);
+        match run(&source, &mut interpreter) {
+            Ok(()) => {}
+            Err(Error::Exit(code)) => exit(code),
+            Err(e) => error!("{}", e),
         };
         source = String::from("");
         print!("> ");
@@ -107,13 +437,22 @@ fn run_prompt() -> io::Result<()> {
     Ok(())
 }
 
-fn run(source: &str, interpreter: &mut Interpreter) -> Result<()> {
+// Note: the lexer has logged its errors already, the first one stops the program
+fn tokenize(source: &str) -> Result<Vec<Token>> {
     let mut lexer = lexer::Lexer::new(String::from(source));
     let tokens = lexer.tokenize_all();
+    match lexer.errors.first() {
+        Some((_, message)) => Err(Error::ParseError(message.clone())),
+        None => Ok(tokens),
+    }
+}
+
+fn run(source: &str, interpreter: &mut Interpreter) -> Result<()> {
+    let tokens = tokenize(source)?;
     let mut parser = Parser::new(tokens);
     let statements = match parser.parse() {
         Ok(result) => result,
-        _ => return Err(Error::ParseError(String::from("parse error"))),
+        Err(e) => return Err(Error::ParseError(e.0)),
     };
 
     let mut resolver = Resolver::new(interpreter);
@@ -124,3 +463,29 @@ fn run(source: &str, interpreter: &mut Interpreter) -> Result<()> {
     interpreter.clone().interpret(statements)
     // println!("Evaluated result: {:?}", result);
 }
+
+// Note: the static checks only look at programs which would be accepted by `run`
+fn parse_and_resolve(source: &str, interpreter: &mut Interpreter) -> Result<(Vec<Stmt>, Symbols)> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser::new(tokens);
+    let statements = match parser.parse() {
+        Ok(result) => result,
+        Err(e) => return Err(Error::ParseError(e.0)),
+    };
+
+    let mut resolver = Resolver::new(interpreter);
+    resolver.resolve_statements(&statements)?;
+    Ok((statements, resolver.into_symbols()))
+}
+
+fn lint(source: &str, interpreter: &mut Interpreter) -> Result<Vec<Warning>> {
+    let (statements, symbols) = parse_and_resolve(source, interpreter)?;
+    let linter = Linter::new(&interpreter.globals.borrow());
+    Ok(linter.lint(&statements, &symbols, source))
+}
+
+fn check(source: &str, interpreter: &mut Interpreter) -> Result<Vec<TypeError>> {
+    let (statements, _) = parse_and_resolve(source, interpreter)?;
+    let checker = TypeChecker::new(&interpreter.globals.borrow());
+    Ok(checker.check(&statements))
+}
@@ -0,0 +1,164 @@
+use super::callable::{NativeFn, NativeFunction};
+use super::environment::Environment;
+use super::error::{Error, Result};
+use super::interpreter::Interpreter;
+use super::object::Object;
+use super::token::{Literal, Token};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod io;
+pub mod list;
+mod math;
+pub mod process;
+pub mod random;
+mod reflect;
+pub mod string;
+mod time;
+
+pub fn define_natives(globals: &Environment) {
+    define(globals, "int", 1, int);
+    define(globals, "float", 1, float);
+    define(globals, "str", 1, str);
+    define(globals, "num", 1, num);
+    math::define_natives(globals);
+    random::define_natives(globals);
+    io::define_natives(globals);
+    process::define_natives(globals);
+    time::define_natives(globals);
+    reflect::define_natives(globals);
+}
+
+fn define(globals: &Environment, name: &'static str, arity: usize, function: NativeFn) {
+    globals.define(
+        String::from(name),
+        &Object::Native(NativeFunction::new(name, arity, function)),
+    );
+}
+
+fn string_argument(paren: &Token, function: &str, argument: &Object) -> Result<String> {
+    match argument {
+        Object::Literal(Literal::String(s)) => Ok(s.clone()),
+        _ => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("{}() expects a string but got {}.", function, argument),
+        )),
+    }
+}
+
+fn integer_argument(paren: &Token, function: &str, argument: &Object) -> Result<isize> {
+    match argument {
+        Object::Literal(Literal::Isize(i)) => Ok(*i),
+        _ => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("{}() expects an integer but got {}.", function, argument),
+        )),
+    }
+}
+
+fn list_argument(
+    paren: &Token,
+    function: &str,
+    argument: &Object,
+) -> Result<Rc<RefCell<Vec<Object>>>> {
+    match argument {
+        Object::List(list) => Ok(Rc::clone(list)),
+        _ => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("{}() expects a list but got {}.", function, argument),
+        )),
+    }
+}
+
+// Note: integers are widened so natives can accept either kind of number
+fn number_argument(paren: &Token, function: &str, argument: &Object) -> Result<f64> {
+    match argument {
+        Object::Literal(Literal::Isize(i)) => Ok(*i as f64),
+        Object::Literal(Literal::Float(f)) => Ok(*f),
+        _ => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("{}() expects a number but got {}.", function, argument),
+        )),
+    }
+}
+
+// Note: floats are truncated toward zero and strings must hold an integer literal
+fn int(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    match &arguments[0] {
+        Object::Literal(Literal::Isize(i)) => Ok(Object::Literal(Literal::Isize(*i))),
+        Object::Literal(Literal::Float(f)) => {
+            let truncated = f.trunc();
+            if truncated.is_finite()
+                && truncated >= isize::MIN as f64
+                && truncated < isize::MAX as f64
+            {
+                Ok(Object::Literal(Literal::Isize(truncated as isize)))
+            } else {
+                Err(Error::RuntimeError(
+                    paren.clone(),
+                    format!("Cannot convert {:?} to int.", f),
+                ))
+            }
+        }
+        Object::Literal(Literal::String(s)) => match s.trim().parse::<isize>() {
+            Ok(i) => Ok(Object::Literal(Literal::Isize(i))),
+            Err(_) => Err(Error::RuntimeError(
+                paren.clone(),
+                format!("Cannot convert \"{}\" to int.", s),
+            )),
+        },
+        other => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("Cannot convert {} to int.", other),
+        )),
+    }
+}
+
+fn float(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    match &arguments[0] {
+        Object::Literal(Literal::Isize(i)) => Ok(Object::Literal(Literal::Float(*i as f64))),
+        Object::Literal(Literal::Float(f)) => Ok(Object::Literal(Literal::Float(*f))),
+        Object::Literal(Literal::String(s)) => match s.trim().parse::<f64>() {
+            Ok(f) => Ok(Object::Literal(Literal::Float(f))),
+            Err(_) => Err(Error::RuntimeError(
+                paren.clone(),
+                format!("Cannot convert \"{}\" to float.", s),
+            )),
+        },
+        other => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("Cannot convert {} to float.", other),
+        )),
+    }
+}
+
+fn str(interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    Ok(Object::Literal(Literal::String(
+        interpreter.stringify(&arguments[0])?,
+    )))
+}
+
+// Note: integers stay integers, anything with a fraction or exponent becomes a float
+fn num(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    match &arguments[0] {
+        Object::Literal(Literal::Isize(i)) => Ok(Object::Literal(Literal::Isize(*i))),
+        Object::Literal(Literal::Float(f)) => Ok(Object::Literal(Literal::Float(*f))),
+        Object::Literal(Literal::String(s)) => {
+            let trimmed = s.trim();
+            if let Ok(i) = trimmed.parse::<isize>() {
+                return Ok(Object::Literal(Literal::Isize(i)));
+            }
+            match trimmed.parse::<f64>() {
+                Ok(f) => Ok(Object::Literal(Literal::Float(f))),
+                Err(_) => Err(Error::RuntimeError(
+                    paren.clone(),
+                    format!("Cannot convert \"{}\" to number.", s),
+                )),
+            }
+        }
+        other => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("Cannot convert {} to number.", other),
+        )),
+    }
+}
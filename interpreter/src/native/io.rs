@@ -0,0 +1,165 @@
+use super::{define, string_argument};
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+pub fn define_natives(globals: &Environment) {
+    define(globals, "readLine", 0, read_line);
+    define(globals, "readFile", 1, read_file);
+    define(globals, "writeFile", 2, write_file);
+    define(globals, "appendFile", 2, append_file);
+    define(globals, "removeFile", 1, remove_file);
+    define(globals, "fileExists", 1, file_exists);
+    define(globals, "listDir", 1, list_dir);
+    define(globals, "eprint", 1, eprint);
+}
+
+// Note: every native here goes through this so a host can switch off I/O in one place
+fn check_allowed(interpreter: &Interpreter, paren: &Token, function: &str) -> Result<()> {
+    if interpreter.allow_io {
+        Ok(())
+    } else {
+        Err(Error::RuntimeError(
+            paren.clone(),
+            format!("{}() is disabled because I/O is not allowed.", function),
+        ))
+    }
+}
+
+fn io_error(paren: &Token, function: &str, path: &str, error: io::Error) -> Error {
+    Error::RuntimeError(
+        paren.clone(),
+        format!("{}() failed for \"{}\": {}.", function, path, error),
+    )
+}
+
+// Note: returns nil at the end of input, the trailing newline is stripped
+fn read_line(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    _arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "readLine")?;
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) => Ok(Object::Literal(Literal::None)),
+        Ok(_) => {
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            Ok(Object::Literal(Literal::String(line)))
+        }
+        Err(e) => Err(io_error(paren, "readLine", "stdin", e)),
+    }
+}
+
+fn read_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "readFile")?;
+    let path = string_argument(paren, "readFile", &arguments[0])?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(Object::Literal(Literal::String(contents))),
+        Err(e) => Err(io_error(paren, "readFile", &path, e)),
+    }
+}
+
+fn write_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "writeFile")?;
+    let path = string_argument(paren, "writeFile", &arguments[0])?;
+    let contents = string_argument(paren, "writeFile", &arguments[1])?;
+    match fs::write(&path, contents) {
+        Ok(()) => Ok(Object::Literal(Literal::None)),
+        Err(e) => Err(io_error(paren, "writeFile", &path, e)),
+    }
+}
+
+fn append_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "appendFile")?;
+    let path = string_argument(paren, "appendFile", &arguments[0])?;
+    let contents = string_argument(paren, "appendFile", &arguments[1])?;
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+    match result {
+        Ok(()) => Ok(Object::Literal(Literal::None)),
+        Err(e) => Err(io_error(paren, "appendFile", &path, e)),
+    }
+}
+
+fn remove_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "removeFile")?;
+    let path = string_argument(paren, "removeFile", &arguments[0])?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(Object::Literal(Literal::None)),
+        Err(e) => Err(io_error(paren, "removeFile", &path, e)),
+    }
+}
+
+fn file_exists(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "fileExists")?;
+    let path = string_argument(paren, "fileExists", &arguments[0])?;
+    Ok(Object::Literal(Literal::Bool(Path::new(&path).exists())))
+}
+
+// Note: entry names only, sorted so output does not depend on the file system
+fn list_dir(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    check_allowed(interpreter, paren, "listDir")?;
+    let path = string_argument(paren, "listDir", &arguments[0])?;
+    let entries = fs::read_dir(&path).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<String>>>()
+    });
+    match entries {
+        Ok(mut names) => {
+            names.sort();
+            let names = names
+                .into_iter()
+                .map(|name| Object::Literal(Literal::String(name)))
+                .collect();
+            Ok(Object::List(Rc::new(RefCell::new(names))))
+        }
+        Err(e) => Err(io_error(paren, "listDir", &path, e)),
+    }
+}
+
+fn eprint(interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    check_allowed(interpreter, paren, "eprint")?;
+    eprintln!("{}", interpreter.stringify(&arguments[0])?);
+    Ok(Object::Literal(Literal::None))
+}
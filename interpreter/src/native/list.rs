@@ -0,0 +1,59 @@
+use super::string_argument;
+use crate::callable::NativeFunction;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn method(name: &str) -> Option<NativeFunction> {
+    match name {
+        "len" => Some(NativeFunction::new("len", 0, len)),
+        "push" => Some(NativeFunction::new("push", 1, push)),
+        "pop" => Some(NativeFunction::new("pop", 0, pop)),
+        "join" => Some(NativeFunction::new("join", 1, join)),
+        _ => None,
+    }
+}
+
+fn receiver(arguments: &[Object]) -> Rc<RefCell<Vec<Object>>> {
+    match &arguments[0] {
+        Object::List(list) => Rc::clone(list),
+        _ => unreachable!(),
+    }
+}
+
+fn len(_interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let count = receiver(&arguments).borrow().len();
+    Ok(Object::Literal(Literal::Isize(count as isize)))
+}
+
+fn push(_interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    receiver(&arguments).borrow_mut().push(arguments[1].clone());
+    Ok(Object::Literal(Literal::None))
+}
+
+fn pop(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    match receiver(&arguments).borrow_mut().pop() {
+        Some(last) => Ok(last),
+        None => Err(Error::RuntimeError(
+            paren.clone(),
+            String::from("pop() called on an empty list."),
+        )),
+    }
+}
+
+fn join(interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let separator = string_argument(paren, "join", &arguments[1])?;
+    let elements = receiver(&arguments).borrow().clone();
+    let joined = elements
+        .iter()
+        .map(|element| match element {
+            Object::Literal(Literal::String(s)) => Ok(s.clone()),
+            _ => interpreter.stringify(element),
+        })
+        .collect::<Result<Vec<String>>>()?
+        .join(&separator);
+    Ok(Object::Literal(Literal::String(joined)))
+}
@@ -0,0 +1,147 @@
+use super::{define, number_argument};
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::f64::consts;
+
+// Note: results are floats like C's math library, except abs, min, max and the rounding
+// functions which keep integers as integers.
+pub fn define_natives(globals: &Environment) {
+    globals.define(
+        String::from("PI"),
+        &Object::Literal(Literal::Float(consts::PI)),
+    );
+    globals.define(
+        String::from("E"),
+        &Object::Literal(Literal::Float(consts::E)),
+    );
+    define(globals, "sqrt", 1, sqrt);
+    define(globals, "pow", 2, pow);
+    define(globals, "abs", 1, abs);
+    define(globals, "floor", 1, floor);
+    define(globals, "ceil", 1, ceil);
+    define(globals, "round", 1, round);
+    define(globals, "min", 2, min);
+    define(globals, "max", 2, max);
+    define(globals, "sin", 1, sin);
+    define(globals, "cos", 1, cos);
+    define(globals, "tan", 1, tan);
+    define(globals, "log", 1, log);
+    define(globals, "exp", 1, exp);
+    define(globals, "isNaN", 1, is_nan);
+    define(globals, "isInfinite", 1, is_infinite);
+}
+
+fn float_function(
+    paren: &Token,
+    name: &str,
+    arguments: &[Object],
+    function: fn(f64) -> f64,
+) -> Result<Object> {
+    let x = number_argument(paren, name, &arguments[0])?;
+    Ok(Object::Literal(Literal::Float(function(x))))
+}
+
+// Note: an integer is already whole, so it is returned unchanged
+fn rounding_function(
+    paren: &Token,
+    name: &str,
+    arguments: &[Object],
+    function: fn(f64) -> f64,
+) -> Result<Object> {
+    match &arguments[0] {
+        Object::Literal(Literal::Isize(i)) => Ok(Object::Literal(Literal::Isize(*i))),
+        _ => float_function(paren, name, arguments, function),
+    }
+}
+
+fn sqrt(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    float_function(paren, "sqrt", &arguments, f64::sqrt)
+}
+
+fn pow(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let base = number_argument(paren, "pow", &arguments[0])?;
+    let exponent = number_argument(paren, "pow", &arguments[1])?;
+    Ok(Object::Literal(Literal::Float(base.powf(exponent))))
+}
+
+fn abs(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    match &arguments[0] {
+        Object::Literal(Literal::Isize(i)) => match i.checked_abs() {
+            Some(a) => Ok(Object::Literal(Literal::Isize(a))),
+            None => Err(Error::RuntimeError(
+                paren.clone(),
+                String::from("Integer overflow."),
+            )),
+        },
+        _ => float_function(paren, "abs", &arguments, f64::abs),
+    }
+}
+
+fn floor(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    rounding_function(paren, "floor", &arguments, f64::floor)
+}
+
+fn ceil(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    rounding_function(paren, "ceil", &arguments, f64::ceil)
+}
+
+// Note: halfway cases round away from zero
+fn round(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    rounding_function(paren, "round", &arguments, f64::round)
+}
+
+fn min(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let a = number_argument(paren, "min", &arguments[0])?;
+    let b = number_argument(paren, "min", &arguments[1])?;
+    if a.is_nan() || b.is_nan() {
+        return Ok(Object::Literal(Literal::Float(f64::NAN)));
+    }
+    Ok(arguments[if b < a { 1 } else { 0 }].clone())
+}
+
+fn max(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let a = number_argument(paren, "max", &arguments[0])?;
+    let b = number_argument(paren, "max", &arguments[1])?;
+    if a.is_nan() || b.is_nan() {
+        return Ok(Object::Literal(Literal::Float(f64::NAN)));
+    }
+    Ok(arguments[if b > a { 1 } else { 0 }].clone())
+}
+
+fn sin(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    float_function(paren, "sin", &arguments, f64::sin)
+}
+
+fn cos(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    float_function(paren, "cos", &arguments, f64::cos)
+}
+
+fn tan(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    float_function(paren, "tan", &arguments, f64::tan)
+}
+
+// Note: natural logarithm
+fn log(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    float_function(paren, "log", &arguments, f64::ln)
+}
+
+fn exp(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    float_function(paren, "exp", &arguments, f64::exp)
+}
+
+fn is_nan(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let x = number_argument(paren, "isNaN", &arguments[0])?;
+    Ok(Object::Literal(Literal::Bool(x.is_nan())))
+}
+
+fn is_infinite(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let x = number_argument(paren, "isInfinite", &arguments[0])?;
+    Ok(Object::Literal(Literal::Bool(x.is_infinite())))
+}
@@ -0,0 +1,49 @@
+use super::{define, integer_argument, string_argument};
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::env;
+use std::rc::Rc;
+
+pub fn define_natives(globals: &Environment) {
+    define_args(globals, Vec::new());
+    define(globals, "getenv", 1, getenv);
+    define(globals, "exit", 1, exit);
+}
+
+// Note: the host replaces the empty default with the arguments that follow the script
+pub fn define_args(globals: &Environment, args: Vec<String>) {
+    let args = args
+        .into_iter()
+        .map(|arg| Object::Literal(Literal::String(arg)))
+        .collect();
+    globals.define(
+        String::from("args"),
+        &Object::List(Rc::new(RefCell::new(args))),
+    );
+}
+
+// Note: nil when the variable is unset or not valid unicode
+fn getenv(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let name = string_argument(paren, "getenv", &arguments[0])?;
+    match env::var(name) {
+        Ok(value) => Ok(Object::Literal(Literal::String(value))),
+        Err(_) => Ok(Object::Literal(Literal::None)),
+    }
+}
+
+// Note: unwinds as an error so `finally` blocks run, `catch` never sees it
+fn exit(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let code = integer_argument(paren, "exit", &arguments[0])?;
+    match i32::try_from(code) {
+        Ok(code) => Err(Error::Exit(code)),
+        Err(_) => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("exit() code {} is out of range.", code),
+        )),
+    }
+}
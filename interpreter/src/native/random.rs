@@ -0,0 +1,126 @@
+use super::{define, integer_argument, list_argument};
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::cell::Cell;
+use std::time::SystemTime;
+
+// Note: SplitMix64, small and deterministic for a given seed on every platform
+#[derive(Debug)]
+pub struct Rng {
+    state: Cell<u64>,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: Cell::new(seed),
+        }
+    }
+
+    pub fn from_time() -> Rng {
+        let nanos = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(n) => n.as_nanos() as u64,
+            Err(e) => e.duration().as_nanos() as u64,
+        };
+        Rng::new(nanos)
+    }
+
+    pub fn seed(&self, seed: u64) {
+        self.state.set(seed);
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        let state = self.state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        self.state.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Note: uniform in [0, 1) using the top 53 bits
+    pub fn next_float(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Note: uniform in [0, bound) by multiply-shift, bound must not be zero
+    pub fn next_below(&self, bound: u128) -> u128 {
+        (u128::from(self.next_u64()) * bound) >> 64
+    }
+}
+
+pub fn define_natives(globals: &Environment) {
+    define(globals, "random", 0, random);
+    define(globals, "randomInt", 2, random_int);
+    define(globals, "shuffle", 1, shuffle);
+    define(globals, "choice", 1, choice);
+    define(globals, "seedRandom", 1, seed_random);
+}
+
+fn random(
+    interpreter: &mut Interpreter,
+    _paren: &Token,
+    _arguments: Vec<Object>,
+) -> Result<Object> {
+    Ok(Object::Literal(Literal::Float(
+        interpreter.rng.next_float(),
+    )))
+}
+
+// Note: both bounds are inclusive
+fn random_int(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let lo = integer_argument(paren, "randomInt", &arguments[0])?;
+    let hi = integer_argument(paren, "randomInt", &arguments[1])?;
+    if lo > hi {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            format!("randomInt() range {}..{} is empty.", lo, hi),
+        ));
+    }
+    let span = (hi as i128 - lo as i128 + 1) as u128;
+    let offset = interpreter.rng.next_below(span);
+    Ok(Object::Literal(Literal::Isize(
+        (lo as i128 + offset as i128) as isize,
+    )))
+}
+
+// Note: shuffles in place with Fisher-Yates
+fn shuffle(interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let list = list_argument(paren, "shuffle", &arguments[0])?;
+    let mut elements = list.borrow_mut();
+    for i in (1..elements.len()).rev() {
+        let j = interpreter.rng.next_below(i as u128 + 1) as usize;
+        elements.swap(i, j);
+    }
+    Ok(Object::Literal(Literal::None))
+}
+
+fn choice(interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let list = list_argument(paren, "choice", &arguments[0])?;
+    let elements = list.borrow();
+    if elements.is_empty() {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            String::from("choice() called on an empty list."),
+        ));
+    }
+    let index = interpreter.rng.next_below(elements.len() as u128) as usize;
+    Ok(elements[index].clone())
+}
+
+fn seed_random(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let seed = integer_argument(paren, "seedRandom", &arguments[0])?;
+    interpreter.rng.seed(seed as u64);
+    Ok(Object::Literal(Literal::None))
+}
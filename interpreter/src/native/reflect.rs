@@ -0,0 +1,182 @@
+use super::{define, string_argument};
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::lox_instance::LoxInstance;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn define_natives(globals: &Environment) {
+    define(globals, "type", 1, type_of);
+    define(globals, "fields", 1, fields);
+    define(globals, "methods", 1, methods);
+    define(globals, "hasField", 2, has_field);
+    define(globals, "getField", 2, get_field);
+    define(globals, "setField", 3, set_field);
+    define(globals, "superclass", 1, superclass);
+    define(globals, "classOf", 1, class_of);
+}
+
+fn instance_argument(paren: &Token, function: &str, argument: &Object) -> Result<LoxInstance> {
+    match argument {
+        Object::Instance(instance) => Ok(instance.clone()),
+        _ => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("{}() expects an instance but got {}.", function, argument),
+        )),
+    }
+}
+
+// Note: private `#name` fields are stored as `Class#name` and reachable only through `this` in
+// their class, the resolver cannot see names built at runtime so they are rejected here
+fn field_name_argument(paren: &Token, function: &str, argument: &Object) -> Result<String> {
+    let name = string_argument(paren, function, argument)?;
+    if name.contains('#') {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            format!(
+                "{}() cannot access private field '{}' outside its class.",
+                function, name
+            ),
+        ));
+    }
+    Ok(name)
+}
+
+fn names(names: Vec<String>) -> Object {
+    let names = names
+        .into_iter()
+        .map(|name| Object::Literal(Literal::String(name)))
+        .collect();
+    Object::List(Rc::new(RefCell::new(names)))
+}
+
+// Note: a token for the property so lookups report errors like `.name` would
+fn property(paren: &Token, name: String) -> Token {
+    Token {
+        lexeme: name,
+        ..paren.clone()
+    }
+}
+
+// Note: instances report the name of their class
+fn type_of(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let name = match &arguments[0] {
+        Object::Literal(Literal::None) => String::from("nil"),
+        Object::Literal(Literal::Bool(_)) => String::from("bool"),
+        Object::Literal(Literal::Isize(_)) | Object::Literal(Literal::Float(_)) => {
+            String::from("number")
+        }
+        Object::Literal(Literal::String(_)) => String::from("string"),
+        Object::Func(_) | Object::Native(_) => String::from("function"),
+        Object::Class(_) => String::from("class"),
+        Object::Trait(_) => String::from("trait"),
+        Object::Instance(instance) => instance.class().name.clone(),
+        Object::List(_) => String::from("list"),
+    };
+    Ok(Object::Literal(Literal::String(name)))
+}
+
+fn fields(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let instance = instance_argument(paren, "fields", &arguments[0])?;
+    let mut field_names: Vec<String> = instance
+        .fields
+        .borrow()
+        .keys()
+        .filter(|name| !name.contains('#'))
+        .cloned()
+        .collect();
+    field_names.sort();
+    Ok(names(field_names))
+}
+
+fn methods(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    match &arguments[0] {
+        Object::Class(class) => Ok(names(class.method_names())),
+        Object::Instance(instance) => Ok(names(instance.class().method_names())),
+        other => Err(Error::RuntimeError(
+            paren.clone(),
+            format!(
+                "methods() expects a class or an instance but got {}.",
+                other
+            ),
+        )),
+    }
+}
+
+fn has_field(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let instance = instance_argument(paren, "hasField", &arguments[0])?;
+    let name = field_name_argument(paren, "hasField", &arguments[1])?;
+    let found = instance.fields.borrow().contains_key(&name);
+    Ok(Object::Literal(Literal::Bool(found)))
+}
+
+// Note: goes through getters and methods like a `.name` expression does
+fn get_field(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let instance = instance_argument(paren, "getField", &arguments[0])?;
+    let name = field_name_argument(paren, "getField", &arguments[1])?;
+    interpreter.get_property(Object::Instance(instance), &property(paren, name))
+}
+
+// Note: goes through setters like an assignment to `.name` does
+fn set_field(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let instance = instance_argument(paren, "setField", &arguments[0])?;
+    let name = field_name_argument(paren, "setField", &arguments[1])?;
+    interpreter.set_property(
+        Object::Instance(instance),
+        &property(paren, name),
+        &arguments[2],
+    )?;
+    Ok(arguments[2].clone())
+}
+
+fn superclass(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    match &arguments[0] {
+        Object::Class(class) => match class.super_class() {
+            Some(super_class) => Ok(Object::Class(Rc::clone(super_class))),
+            None => Ok(Object::Literal(Literal::None)),
+        },
+        other => Err(Error::RuntimeError(
+            paren.clone(),
+            format!("superclass() expects a class but got {}.", other),
+        )),
+    }
+}
+
+// Note: nil for values which are not instances
+fn class_of(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    match &arguments[0] {
+        Object::Instance(instance) => Ok(Object::Class(Rc::clone(instance.class()))),
+        _ => Ok(Object::Literal(Literal::None)),
+    }
+}
@@ -0,0 +1,199 @@
+use super::{integer_argument, string_argument};
+use crate::callable::NativeFunction;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Note: the longest string repeat() builds, in bytes
+const MAX_REPEAT_LENGTH: usize = 1 << 30;
+
+// Note: positions and lengths count characters, not bytes
+pub fn method(name: &str) -> Option<NativeFunction> {
+    match name {
+        "len" => Some(NativeFunction::new("len", 0, len)),
+        "substring" => Some(NativeFunction::new("substring", 2, substring)),
+        "indexOf" => Some(NativeFunction::new("indexOf", 1, index_of)),
+        "split" => Some(NativeFunction::new("split", 1, split)),
+        "trim" => Some(NativeFunction::new("trim", 0, trim)),
+        "upper" => Some(NativeFunction::new("upper", 0, upper)),
+        "lower" => Some(NativeFunction::new("lower", 0, lower)),
+        "replace" => Some(NativeFunction::new("replace", 2, replace)),
+        "startsWith" => Some(NativeFunction::new("startsWith", 1, starts_with)),
+        "endsWith" => Some(NativeFunction::new("endsWith", 1, ends_with)),
+        "charAt" => Some(NativeFunction::new("charAt", 1, char_at)),
+        "repeat" => Some(NativeFunction::new("repeat", 1, repeat)),
+        _ => None,
+    }
+}
+
+fn receiver(arguments: &[Object]) -> &str {
+    match &arguments[0] {
+        Object::Literal(Literal::String(s)) => s,
+        _ => unreachable!(),
+    }
+}
+
+fn string(s: String) -> Result<Object> {
+    Ok(Object::Literal(Literal::String(s)))
+}
+
+fn len(_interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let count = receiver(&arguments).chars().count();
+    Ok(Object::Literal(Literal::Isize(count as isize)))
+}
+
+fn substring(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let s = receiver(&arguments);
+    let start = integer_argument(paren, "substring", &arguments[1])?;
+    let end = integer_argument(paren, "substring", &arguments[2])?;
+    let count = s.chars().count() as isize;
+    if start < 0 || end < start || end > count {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            format!(
+                "substring() range {}..{} is out of bounds for length {}.",
+                start, end, count
+            ),
+        ));
+    }
+    string(
+        s.chars()
+            .skip(start as usize)
+            .take((end - start) as usize)
+            .collect(),
+    )
+}
+
+fn index_of(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let s = receiver(&arguments);
+    let needle = string_argument(paren, "indexOf", &arguments[1])?;
+    let index = match s.find(&needle) {
+        Some(byte_index) => s[..byte_index].chars().count() as isize,
+        None => -1,
+    };
+    Ok(Object::Literal(Literal::Isize(index)))
+}
+
+// Note: an empty separator splits into characters
+fn split(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let s = receiver(&arguments);
+    let separator = string_argument(paren, "split", &arguments[1])?;
+    let parts: Vec<Object> = if separator.is_empty() {
+        s.chars()
+            .map(|c| Object::Literal(Literal::String(c.to_string())))
+            .collect()
+    } else {
+        s.split(separator.as_str())
+            .map(|part| Object::Literal(Literal::String(part.to_string())))
+            .collect()
+    };
+    Ok(Object::List(Rc::new(RefCell::new(parts))))
+}
+
+fn trim(_interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    string(receiver(&arguments).trim().to_string())
+}
+
+fn upper(_interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    string(receiver(&arguments).to_uppercase())
+}
+
+fn lower(_interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    string(receiver(&arguments).to_lowercase())
+}
+
+fn replace(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let s = receiver(&arguments);
+    let from = string_argument(paren, "replace", &arguments[1])?;
+    let to = string_argument(paren, "replace", &arguments[2])?;
+    if from.is_empty() {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            String::from("replace() cannot replace an empty string."),
+        ));
+    }
+    string(s.replace(&from, &to))
+}
+
+fn starts_with(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let prefix = string_argument(paren, "startsWith", &arguments[1])?;
+    Ok(Object::Literal(Literal::Bool(
+        receiver(&arguments).starts_with(&prefix),
+    )))
+}
+
+fn ends_with(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let suffix = string_argument(paren, "endsWith", &arguments[1])?;
+    Ok(Object::Literal(Literal::Bool(
+        receiver(&arguments).ends_with(&suffix),
+    )))
+}
+
+fn char_at(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let s = receiver(&arguments);
+    let index = integer_argument(paren, "charAt", &arguments[1])?;
+    let found = if index < 0 {
+        None
+    } else {
+        s.chars().nth(index as usize)
+    };
+    match found {
+        Some(c) => string(c.to_string()),
+        None => Err(Error::RuntimeError(
+            paren.clone(),
+            format!(
+                "charAt() index {} is out of bounds for length {}.",
+                index,
+                s.chars().count()
+            ),
+        )),
+    }
+}
+
+fn repeat(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let s = receiver(&arguments);
+    let count = integer_argument(paren, "repeat", &arguments[1])?;
+    if count < 0 {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            format!("repeat() count must not be negative but got {}.", count),
+        ));
+    }
+    match s.len().checked_mul(count as usize) {
+        Some(length) if length <= MAX_REPEAT_LENGTH => string(s.repeat(count as usize)),
+        _ => Err(Error::RuntimeError(
+            paren.clone(),
+            format!(
+                "repeat() result would be longer than {} bytes.",
+                MAX_REPEAT_LENGTH
+            ),
+        )),
+    }
+}
@@ -0,0 +1,132 @@
+use super::{define, number_argument, string_argument};
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::{Literal, Token};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub fn define_natives(globals: &Environment) {
+    define(globals, "clock", 0, clock);
+    define(globals, "now", 0, now);
+    define(globals, "sleep", 1, sleep);
+    define(globals, "formatTime", 2, format_time);
+}
+
+// Note: monotonic seconds since the interpreter started, only differences are meaningful
+fn clock(interpreter: &mut Interpreter, _paren: &Token, _arguments: Vec<Object>) -> Result<Object> {
+    let elapsed = interpreter.started_at.elapsed().as_secs_f64();
+    Ok(Object::Literal(Literal::Float(elapsed)))
+}
+
+// Note: wall-clock seconds since the Unix epoch, negative if the system clock is before it
+fn now(_interpreter: &mut Interpreter, _paren: &Token, _arguments: Vec<Object>) -> Result<Object> {
+    let seconds = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    };
+    Ok(Object::Literal(Literal::Float(seconds)))
+}
+
+fn sleep(_interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Result<Object> {
+    let seconds = number_argument(paren, "sleep", &arguments[0])?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            format!(
+                "sleep() expects a non-negative duration but got {}.",
+                seconds
+            ),
+        ));
+    }
+    let duration = Duration::try_from_secs_f64(seconds).map_err(|_| {
+        Error::RuntimeError(
+            paren.clone(),
+            format!("sleep() duration {} is too large.", seconds),
+        )
+    })?;
+    thread::sleep(duration);
+    Ok(Object::Literal(Literal::None))
+}
+
+// Note: UTC only, supports %Y %m %d %H %M %S %j %a %b and %%
+fn format_time(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<Object>,
+) -> Result<Object> {
+    let epoch = number_argument(paren, "formatTime", &arguments[0])?;
+    let format = string_argument(paren, "formatTime", &arguments[1])?;
+    if !epoch.is_finite() {
+        return Err(Error::RuntimeError(
+            paren.clone(),
+            format!("formatTime() expects a finite time but got {}.", epoch),
+        ));
+    }
+    let seconds = epoch.floor() as i64;
+    let days = seconds.div_euclid(86_400);
+    let second_of_day = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let day_of_year = days - days_from_civil(year, 1, 1) + 1;
+    let weekday = (days + 4).rem_euclid(7) as usize;
+
+    let mut result = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => result.push_str(&format!("{:04}", year)),
+            Some('m') => result.push_str(&format!("{:02}", month)),
+            Some('d') => result.push_str(&format!("{:02}", day)),
+            Some('H') => result.push_str(&format!("{:02}", second_of_day / 3600)),
+            Some('M') => result.push_str(&format!("{:02}", second_of_day % 3600 / 60)),
+            Some('S') => result.push_str(&format!("{:02}", second_of_day % 60)),
+            Some('j') => result.push_str(&format!("{:03}", day_of_year)),
+            Some('a') => result.push_str(WEEKDAYS[weekday]),
+            Some('b') => result.push_str(MONTHS[month as usize - 1]),
+            Some('%') => result.push('%'),
+            other => {
+                let directive = other.map_or(String::new(), |c| c.to_string());
+                return Err(Error::RuntimeError(
+                    paren.clone(),
+                    format!("formatTime() does not support '%{}'.", directive),
+                ));
+            }
+        }
+    }
+    Ok(Object::Literal(Literal::String(result)))
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Note: proleptic Gregorian calendar conversions from Howard Hinnant's date algorithms
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * mp + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
@@ -1,16 +1,21 @@
 use super::callable;
 use super::lox_class;
 use super::lox_instance;
+use super::lox_trait;
 use super::token;
+use std::cell::RefCell;
 use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum Object {
     Literal(token::Literal),
     Func(callable::LoxFunction),
-    Clock(callable::Clock),
-    Class(lox_class::LoxClass),
+    Native(callable::NativeFunction),
+    Class(Rc<lox_class::LoxClass>),
     Instance(lox_instance::LoxInstance),
+    Trait(Rc<lox_trait::LoxTrait>),
+    List(Rc<RefCell<Vec<Object>>>),
 }
 
 impl fmt::Display for Object {
@@ -18,9 +23,36 @@ impl fmt::Display for Object {
         match self {
             Object::Literal(l) => write!(f, "{}", l),
             Object::Func(l) => write!(f, "{}", l),
-            Object::Clock(l) => write!(f, "{}", l),
+            Object::Native(l) => write!(f, "{}", l),
             Object::Class(l) => write!(f, "{}", l),
             Object::Instance(l) => write!(f, "{}", l),
+            Object::Trait(l) => write!(f, "{}", l),
+            Object::List(l) => write_list(f, l, &mut Vec::new()),
         }
     }
 }
+
+// Note: a list which contains itself is written as `[...]` where it recurs
+fn write_list(
+    f: &mut fmt::Formatter,
+    list: &Rc<RefCell<Vec<Object>>>,
+    visiting: &mut Vec<*const RefCell<Vec<Object>>>,
+) -> fmt::Result {
+    if visiting.contains(&Rc::as_ptr(list)) {
+        return write!(f, "[...]");
+    }
+    visiting.push(Rc::as_ptr(list));
+    write!(f, "[")?;
+    for (i, element) in list.borrow().iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        match element {
+            Object::Literal(token::Literal::String(s)) => write!(f, "{:?}", s)?,
+            Object::List(inner) => write_list(f, inner, visiting)?,
+            _ => write!(f, "{}", element)?,
+        }
+    }
+    visiting.pop();
+    write!(f, "]")
+}
@@ -3,9 +3,11 @@ use super::expr::Expr;
 use super::stmt::Stmt;
 use super::token::{Literal, Token};
 use super::token_type::TokenType;
+use super::types::TypeAnnotation;
 
 #[derive(Debug, Clone)]
-pub struct ParserError(String);
+// Note: the token is where the error was found, for editors
+pub struct ParserError(pub String, pub Token);
 
 type ParseResult<T> = Result<T, ParserError>;
 
@@ -36,7 +38,13 @@ impl Parser {
         let result = if self.contains(&[TokenType::Var]) {
             self.var_declaration()
         } else if self.contains(&[TokenType::Class]) {
-            self.class_declaration()
+            self.class_declaration(false)
+        } else if self.peek().lexeme == "sealed" && self.check_next(TokenType::Class) {
+            self.advance();
+            self.advance();
+            self.class_declaration(true)
+        } else if self.contains(&[TokenType::Trait]) {
+            self.trait_declaration()
         } else if self.contains(&[TokenType::Fun]) {
             self.function(String::from("function"))
         } else {
@@ -52,7 +60,7 @@ impl Parser {
         }
     }
 
-    fn class_declaration(&mut self) -> ParseResult<Stmt> {
+    fn class_declaration(&mut self, sealed: bool) -> ParseResult<Stmt> {
         let name = self.consume(TokenType::Identifier, "Expect class name.")?;
         let super_class = if self.contains(&[TokenType::Less]) {
             self.consume(TokenType::Identifier, "Expect superclass name.")?;
@@ -62,21 +70,62 @@ impl Parser {
         } else {
             None
         };
+        let mut traits = vec![];
+        if self.contains(&[TokenType::With]) {
+            loop {
+                self.consume(TokenType::Identifier, "Expect trait name.")?;
+                traits.push(Expr::Variable {
+                    name: self.previous().clone(),
+                });
+                if !self.contains(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
         self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
         let mut methods = vec![];
+        let mut class_methods = vec![];
+        let mut getters = vec![];
+        let mut setters = vec![];
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            let function = self.function(String::from("method"))?;
-            methods.push(function);
+            if self.contains(&[TokenType::Class]) {
+                class_methods.push(self.function(String::from("method"))?);
+            } else if self.check(TokenType::Identifier)
+                && (self.check_next(TokenType::LeftBrace) || self.check_next(TokenType::Colon))
+            {
+                getters.push(self.getter()?);
+            } else if self.peek().lexeme == "set" && self.check_next(TokenType::Identifier) {
+                self.advance();
+                setters.push(self.function(String::from("setter"))?);
+            } else {
+                methods.push(self.function(String::from("method"))?);
+            }
         }
         self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
 
         Ok(Stmt::Class {
             name,
             super_class,
+            traits,
             methods,
+            class_methods,
+            getters,
+            setters,
+            sealed,
         })
     }
 
+    fn trait_declaration(&mut self) -> ParseResult<Stmt> {
+        let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body.")?;
+        let mut methods = vec![];
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(self.function(String::from("method"))?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after trait body.")?;
+        Ok(Stmt::Trait { name, methods })
+    }
+
     fn statement(&mut self) -> ParseResult<Stmt> {
         if self.contains(&[TokenType::For]) {
             return self.for_statement();
@@ -93,6 +142,12 @@ impl Parser {
         if self.contains(&[TokenType::While]) {
             return self.while_statement();
         }
+        if self.contains(&[TokenType::Throw]) {
+            return self.throw_statement();
+        }
+        if self.contains(&[TokenType::Try]) {
+            return self.try_statement();
+        }
         if self.contains(&[TokenType::LeftBrace]) {
             return Ok(Stmt::Block {
                 statements: self.block()?,
@@ -129,12 +184,12 @@ impl Parser {
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
 
         let mut body = self.statement()?;
-        if increment.is_some() {
+        if let Some(increment) = increment {
             body = Stmt::Block {
                 statements: vec![
                     body,
                     Stmt::Expression {
-                        expression: increment.unwrap(),
+                        expression: increment,
                     },
                 ],
             }
@@ -143,9 +198,9 @@ impl Parser {
             condition,
             body: Box::new(body),
         };
-        if initializer.is_some() {
+        if let Some(initializer) = initializer {
             body = Stmt::Block {
-                statements: vec![initializer.unwrap(), body],
+                statements: vec![initializer, body],
             }
         }
         Ok(body)
@@ -170,9 +225,13 @@ impl Parser {
     }
 
     fn print_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
         let value = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ':' after value.")?;
-        Ok(Stmt::Print { expression: value })
+        Ok(Stmt::Print {
+            keyword,
+            expression: value,
+        })
     }
 
     fn return_statemet(&mut self) -> ParseResult<Stmt> {
@@ -188,8 +247,55 @@ impl Parser {
         Ok(Stmt::Return { keyword, value })
     }
 
+    fn throw_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw { keyword, value })
+    }
+
+    fn try_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let try_branch = self.block()?;
+
+        let (catch_name, catch_branch) = if self.contains(&[TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenType::Identifier, "Expect exception variable name.")?;
+            self.consume(
+                TokenType::RightParen,
+                "Expect ')' after exception variable.",
+            )?;
+            self.consume(TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            (Some(name), self.block()?)
+        } else {
+            (None, vec![])
+        };
+
+        let finally_branch = if self.contains(&[TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch_name.is_none() && finally_branch.is_none() {
+            return Err(Parser::error(
+                keyword,
+                "Expect 'catch' or 'finally' after try block.",
+            ));
+        }
+        Ok(Stmt::Try {
+            try_branch,
+            catch_name,
+            catch_branch,
+            finally_branch,
+        })
+    }
+
     fn var_declaration(&mut self) -> ParseResult<Stmt> {
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let type_annotation = self.type_annotation()?;
 
         let initializer = if self.contains(&[TokenType::Equal]) {
             self.expression()?
@@ -203,7 +309,24 @@ impl Parser {
             TokenType::Semicolon,
             "Expect ';' after variable declaration.",
         )?;
-        Ok(Stmt::Var { name, initializer })
+        Ok(Stmt::Var {
+            name,
+            type_annotation,
+            initializer,
+        })
+    }
+
+    // Note: `: name` or `: name?`, nil and fun are keywords so they are accepted separately
+    fn type_annotation(&mut self) -> ParseResult<Option<TypeAnnotation>> {
+        if !self.contains(&[TokenType::Colon]) {
+            return Ok(None);
+        }
+        if !self.contains(&[TokenType::Identifier, TokenType::Nil, TokenType::Fun]) {
+            return Err(Parser::error(self.peek().clone(), "Expect type name."));
+        }
+        let name = self.previous().clone();
+        let nullable = self.contains(&[TokenType::Question]);
+        Ok(Some(TypeAnnotation { name, nullable }))
     }
 
     fn while_statement(&mut self) -> ParseResult<Stmt> {
@@ -227,6 +350,7 @@ impl Parser {
             &format!("Expect '(' after {} name.", kind),
         )?;
         let mut parameters = vec![];
+        let mut param_types = vec![];
         loop {
             if !self.check(TokenType::RightParen) {
                 if parameters.len() >= 255 {
@@ -235,13 +359,15 @@ impl Parser {
                         "Cannot have more than 255 parameters.",
                     ));
                 }
-                parameters.push(self.consume(TokenType::Identifier, "Expect parameter name.")?)
+                parameters.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+                param_types.push(self.type_annotation()?);
             }
             if !self.contains(&[TokenType::Comma]) {
                 break;
             }
         }
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        let return_type = self.type_annotation()?;
 
         self.consume(
             TokenType::LeftBrace,
@@ -251,6 +377,23 @@ impl Parser {
         Ok(Stmt::Function {
             name,
             params: parameters,
+            param_types,
+            return_type,
+            body,
+        })
+    }
+
+    // Note: a getter is a method without a parameter list, e.g. `area { ... }`
+    fn getter(&mut self) -> ParseResult<Stmt> {
+        let name = self.consume(TokenType::Identifier, "Expect getter name.")?;
+        let return_type = self.type_annotation()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before getter body.")?;
+        let body = self.block()?;
+        Ok(Stmt::Function {
+            name,
+            params: vec![],
+            param_types: vec![],
+            return_type,
             body,
         })
     }
@@ -265,7 +408,7 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> ParseResult<Expr> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
 
         if self.contains(&[TokenType::Equal]) {
             let equals = self.previous().clone();
@@ -281,9 +424,64 @@ impl Parser {
                     name,
                     value: Box::new(value),
                 }),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => Ok(Expr::IndexSet {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                }),
                 _ => Err(Parser::error(equals, "Invalid assignment target.")),
             };
         }
+
+        if self.contains(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let value = self.assignment()?;
+            if !Parser::is_assignable(&expr) {
+                return Err(Parser::error(operator, "Invalid assignment target."));
+            }
+            return Ok(Expr::CompoundAssign {
+                target: Box::new(expr),
+                operator,
+                value: Box::new(value),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn is_assignable(expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. }
+        )
+    }
+
+    fn conditional(&mut self) -> ParseResult<Expr> {
+        let expr = self.or()?;
+
+        if self.contains(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional {
+                condition: Box::new(expr),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
+        }
         Ok(expr)
     }
 
@@ -330,22 +528,73 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.addition() {
-            Ok(result) => result,
-            Err(err) => return Err(err),
-        };
+        let mut expr = self.bit_or()?;
 
         while self.contains(&[
             TokenType::Greater,
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            TokenType::Is,
         ]) {
             let operator = self.previous().clone();
-            let right = match self.addition() {
-                Ok(result) => result,
-                Err(err) => return Err(err),
-            };
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn bit_or(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_xor()?;
+        while self.contains(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_and()?;
+        while self.contains(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.shift()?;
+        while self.contains(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.addition()?;
+        while self.contains(&[TokenType::LessLess, TokenType::GreaterGreater]) {
+            let operator = self.previous().clone();
+            let right = self.addition()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
@@ -356,17 +605,11 @@ impl Parser {
     }
 
     fn addition(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.multiplication() {
-            Ok(multiplication) => multiplication,
-            Err(err) => return Err(err),
-        };
+        let mut expr = self.multiplication()?;
 
         while self.contains(&[TokenType::Minus, TokenType::Plus]) {
             let operator = self.previous().clone();
-            let right = match self.multiplication() {
-                Ok(multiplication) => multiplication,
-                Err(err) => return Err(err),
-            };
+            let right = self.multiplication()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
@@ -377,17 +620,16 @@ impl Parser {
     }
 
     fn multiplication(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.unary() {
-            Ok(unary) => unary,
-            Err(err) => return Err(err),
-        };
+        let mut expr = self.unary()?;
 
-        while self.contains(&[TokenType::Slash, TokenType::Star]) {
+        while self.contains(&[
+            TokenType::Slash,
+            TokenType::TildeSlash,
+            TokenType::Star,
+            TokenType::Percent,
+        ]) {
             let operator = self.previous().clone();
-            let right = match self.unary() {
-                Ok(unary) => unary,
-                Err(err) => return Err(err),
-            };
+            let right = self.unary()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
@@ -398,18 +640,59 @@ impl Parser {
     }
 
     fn unary(&mut self) -> ParseResult<Expr> {
-        if self.contains(&[TokenType::Bang, TokenType::Minus]) {
+        if self.contains(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
             let operator = self.previous().clone();
-            let right = match self.unary() {
-                Ok(unary) => unary,
-                Err(err) => return Err(err),
-            };
+            let target = self.unary()?;
+            if !Parser::is_assignable(&target) {
+                return Err(Parser::error(operator, "Invalid increment target."));
+            }
+            return Ok(Expr::Increment {
+                target: Box::new(target),
+                operator,
+                postfix: false,
+            });
+        }
+        if self.contains(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
             return Ok(Expr::Unary {
                 operator,
                 right: Box::new(right),
             });
         }
-        self.call()
+        self.exponent()
+    }
+
+    // Note: right associative and binds tighter than a unary operator on its left,
+    // so `-2 ** 2` is `-(2 ** 2)` and `2 ** -1` is allowed.
+    fn exponent(&mut self) -> ParseResult<Expr> {
+        let expr = self.postfix()?;
+        if self.contains(&[TokenType::StarStar]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> ParseResult<Expr> {
+        let expr = self.call()?;
+        if self.contains(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            if !Parser::is_assignable(&expr) {
+                return Err(Parser::error(operator, "Invalid increment target."));
+            }
+            return Ok(Expr::Increment {
+                target: Box::new(expr),
+                operator,
+                postfix: true,
+            });
+        }
+        Ok(expr)
     }
 
     fn finish_call(&mut self, callee: Expr) -> ParseResult<Expr> {
@@ -440,12 +723,24 @@ impl Parser {
             if self.contains(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
             } else if self.contains(&[TokenType::Dot]) {
-                let name =
-                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                let name = if self.contains(&[TokenType::PrivateIdentifier]) {
+                    self.previous().clone()
+                } else {
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?
+                };
                 expr = Expr::Get {
                     object: Box::new(expr),
                     name,
                 }
+            } else if self.contains(&[TokenType::LeftBracket]) {
+                let bracket = self.previous().clone();
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                }
             } else {
                 break;
             }
@@ -491,6 +786,20 @@ impl Parser {
             });
         }
 
+        if self.contains(&[TokenType::LeftBracket]) {
+            let mut elements = vec![];
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.contains(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List { elements });
+        }
+
         if self.contains(&[TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "Expect')' after expression.")?;
@@ -512,8 +821,8 @@ impl Parser {
     }
 
     fn error(token: Token, message: &str) -> ParserError {
-        parser_error(token, &message);
-        ParserError(String::from(message))
+        parser_error(token.clone(), message);
+        ParserError(String::from(message), token)
     }
 
     fn synchronize(&mut self) {
@@ -526,11 +835,14 @@ impl Parser {
 
             match self.peek().token_type {
                 TokenType::Class
+                | TokenType::Trait
                 | TokenType::Fun
                 | TokenType::Var
                 | TokenType::For
                 | TokenType::If
-                | TokenType::While => return,
+                | TokenType::While
+                | TokenType::Throw
+                | TokenType::Try => return,
                 _ => {
                     self.advance();
                 }
@@ -557,6 +869,13 @@ impl Parser {
         self.peek().token_type == token_type
     }
 
+    fn check_next(&self, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == token_type,
+            None => false,
+        }
+    }
+
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1
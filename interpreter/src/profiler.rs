@@ -0,0 +1,232 @@
+use super::error::Result;
+use super::interpreter::{Hook, Interpreter};
+use super::stmt::Stmt;
+use super::token::Token;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+// Note: how often the line being run is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_micros(100);
+// Note: the report lists only the hottest lines
+const REPORTED_LINES: usize = 20;
+const SCRIPT: &str = "<script>";
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FunctionStats {
+    pub calls: u64,
+    // Note: recursive calls are only counted once, by their outermost call
+    pub inclusive: Duration,
+    pub exclusive: Duration,
+}
+
+#[derive(Debug)]
+struct Frame {
+    function: String,
+    // Note: the callers' functions joined by `;`, the frame of a collapsed stack
+    stack: String,
+    started_at: Instant,
+    children: Duration,
+    line: usize,
+}
+
+// Times the calls of every Lox function and samples the line being run. Functions are named
+// `name:line` after their declaration, so methods of different classes are told apart.
+// Prelude functions are not seen by hooks, so their time goes to their caller's frame and line.
+#[derive(Debug)]
+pub struct Profiler {
+    pub functions: HashMap<String, FunctionStats>,
+    // Note: samples per line of the script
+    pub lines: HashMap<usize, u64>,
+    // Note: exclusive time per collapsed stack
+    pub stacks: HashMap<String, Duration>,
+    frames: Vec<Frame>,
+    // Note: how many frames of each function are running, for recursion
+    active: HashMap<String, usize>,
+    started_at: Instant,
+    total: Duration,
+    next_sample: Instant,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        let now = Instant::now();
+        Profiler {
+            functions: HashMap::new(),
+            lines: HashMap::new(),
+            stacks: HashMap::new(),
+            frames: vec![Frame {
+                function: String::from(SCRIPT),
+                stack: String::from(SCRIPT),
+                started_at: now,
+                children: Duration::default(),
+                line: 0,
+            }],
+            active: HashMap::new(),
+            started_at: now,
+            total: Duration::default(),
+            next_sample: now + SAMPLE_INTERVAL,
+        }
+    }
+
+    // Note: the samples due since the last statement go to the line which was running
+    fn sample(&mut self, now: Instant) {
+        if now < self.next_sample {
+            return;
+        }
+        let due = ((now - self.next_sample).as_micros() / SAMPLE_INTERVAL.as_micros()) as u64 + 1;
+        self.next_sample += SAMPLE_INTERVAL * due as u32;
+        let line = self.frames.last().unwrap().line;
+        if line > 0 {
+            *self.lines.entry(line).or_insert(0) += due;
+        }
+    }
+
+    fn pop_frame(&mut self, now: Instant) {
+        let frame = self.frames.pop().unwrap();
+        let elapsed = now - frame.started_at;
+        let exclusive = elapsed.checked_sub(frame.children).unwrap_or_default();
+        *self.stacks.entry(frame.stack).or_default() += exclusive;
+        if let Some(parent) = self.frames.last_mut() {
+            parent.children += elapsed;
+        }
+        if frame.function == SCRIPT {
+            return;
+        }
+        let active = self.active.get_mut(&frame.function).unwrap();
+        *active -= 1;
+        let outermost = *active == 0;
+        let stats = self.functions.get_mut(&frame.function).unwrap();
+        stats.exclusive += exclusive;
+        if outermost {
+            stats.inclusive += elapsed;
+        }
+    }
+
+    // Stops the clock, closing the calls a call of `exit()` left running.
+    pub fn finish(&mut self) {
+        let now = Instant::now();
+        self.sample(now);
+        while !self.frames.is_empty() {
+            self.pop_frame(now);
+        }
+        self.total = now - self.started_at;
+    }
+
+    // Returns the functions by exclusive time and the hottest lines by samples, with their
+    // source.
+    pub fn report(&self, source: &str) -> String {
+        let mut report = String::new();
+        let samples: u64 = self.lines.values().sum();
+        writeln!(
+            report,
+            "Total time {} ms, {} samples every {} ms",
+            milliseconds(self.total),
+            samples,
+            milliseconds(SAMPLE_INTERVAL)
+        )
+        .unwrap();
+
+        let mut functions: Vec<(&String, &FunctionStats)> = self.functions.iter().collect();
+        functions.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then(a.0.cmp(b.0)));
+        writeln!(report, "\nFunctions by exclusive time:").unwrap();
+        writeln!(
+            report,
+            "{:>10} {:>14} {:>14}  function",
+            "calls", "inclusive ms", "exclusive ms"
+        )
+        .unwrap();
+        for (name, stats) in functions {
+            writeln!(
+                report,
+                "{:>10} {:>14} {:>14}  {}",
+                stats.calls,
+                milliseconds(stats.inclusive),
+                milliseconds(stats.exclusive),
+                name
+            )
+            .unwrap();
+        }
+
+        let mut lines: Vec<(&usize, &u64)> = self.lines.iter().collect();
+        lines.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        writeln!(report, "\nLines by samples:").unwrap();
+        writeln!(report, "{:>10} {:>7}  line", "samples", "%").unwrap();
+        let text: Vec<&str> = source.lines().collect();
+        for (line, count) in lines.into_iter().take(REPORTED_LINES) {
+            writeln!(
+                report,
+                "{:>10} {:>6.1}%  {:>4} | {}",
+                count,
+                *count as f64 * 100.0 / samples as f64,
+                line,
+                text.get(line - 1).map(|text| text.trim()).unwrap_or("")
+            )
+            .unwrap();
+        }
+        report
+    }
+
+    // Returns one `frame;frame;frame weight` line per stack with its exclusive time in
+    // microseconds, the format flamegraph.pl and inferno read.
+    pub fn collapsed_stacks(&self) -> String {
+        let mut stacks: Vec<(&String, u128)> = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| (stack, time.as_micros()))
+            .filter(|(_, micros)| *micros > 0)
+            .collect();
+        stacks.sort();
+        let mut collapsed = String::new();
+        for (stack, micros) in stacks {
+            writeln!(collapsed, "{} {}", stack, micros).unwrap();
+        }
+        collapsed
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        Profiler::new()
+    }
+}
+
+impl Hook for Profiler {
+    fn before_statement(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        _statement: &Stmt,
+        line: usize,
+    ) -> Result<()> {
+        self.sample(Instant::now());
+        self.frames.last_mut().unwrap().line = line;
+        Ok(())
+    }
+
+    fn enter_function(&mut self, name: &Token) {
+        let now = Instant::now();
+        self.sample(now);
+        let function = format!("{}:{}", name.lexeme, name.line);
+        self.functions.entry(function.clone()).or_default().calls += 1;
+        *self.active.entry(function.clone()).or_insert(0) += 1;
+        let caller = self.frames.last().unwrap();
+        let frame = Frame {
+            stack: format!("{};{}", caller.stack, function),
+            line: caller.line,
+            function,
+            started_at: now,
+            children: Duration::default(),
+        };
+        self.frames.push(frame);
+    }
+
+    fn exit_function(&mut self) {
+        let now = Instant::now();
+        self.sample(now);
+        self.pop_frame(now);
+    }
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
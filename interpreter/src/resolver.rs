@@ -7,7 +7,9 @@ use super::lox_class::ClassType;
 use super::stmt::{Acceptor as StmtAcceptor, Stmt, Visitor as StmtVisitor};
 use super::token::Literal;
 use super::token::Token;
-use std::collections::HashMap;
+use super::token_type::TokenType;
+use super::types::TypeAnnotation;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
 pub struct Resolver<'a> {
@@ -15,16 +17,85 @@ pub struct Resolver<'a> {
     pub scopes: Vec<HashMap<String, bool>>,
     current_function: FunctionType,
     current_class: ClassType,
+    // Note: method names of the traits declared in each scope in scopes, to report conflicts
+    traits: Vec<HashMap<String, Vec<String>>>,
+    global_traits: HashMap<String, Vec<String>>,
+    // Note: one entry per enclosing class body, for checking `#name` fields
+    private_fields: Vec<PrivateFields>,
+    symbols: Symbols,
+    // Note: index into symbols.declarations for the names of each scope in scopes
+    declarations: Vec<HashMap<String, usize>>,
+    global_declarations: HashMap<String, usize>,
+    // Note: names not found in any scope and whether they are assigned, matched against the
+    // globals at the end
+    unresolved: Vec<(Token, bool)>,
+}
+
+// Where every name is declared and which declaration each use of a name refers to.
+// Uses of natives and of undeclared globals are left out.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    pub declarations: Vec<Token>,
+    // Note: one per declaration
+    pub kinds: Vec<SymbolKind>,
+    // Note: the use and the index of its declaration
+    pub references: Vec<(Token, usize)>,
+    // Note: indices into references of the uses which assign the name rather than read it
+    pub assignments: HashSet<usize>,
+    // Note: a local declaration and the declaration of an enclosing scope it hides
+    pub shadowed: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Global,
+    Local,
+    Parameter,
+    Catch,
+}
+
+// A private field is declared by assigning it through `this` somewhere in the class body.
+#[derive(Debug, Default)]
+struct PrivateFields {
+    class: String,
+    declared: HashSet<String>,
+    used: Vec<Token>,
+}
+
+impl Symbols {
+    fn add_reference(&mut self, name: Token, declaration: usize, assigns: bool) {
+        if assigns {
+            self.assignments.insert(self.references.len());
+        }
+        self.references.push((name, declaration));
+    }
 }
 
 impl<'a> Resolver<'a> {
-    pub fn new(interpreter: &'a mut Interpreter) -> Resolver {
+    pub fn new(interpreter: &'a mut Interpreter) -> Resolver<'a> {
         Resolver {
             interpreter,
             scopes: Vec::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            traits: Vec::new(),
+            global_traits: HashMap::new(),
+            private_fields: Vec::new(),
+            symbols: Symbols::default(),
+            declarations: Vec::new(),
+            global_declarations: HashMap::new(),
+            unresolved: Vec::new(),
+        }
+    }
+
+    pub fn into_symbols(self) -> Symbols {
+        let mut symbols = self.symbols;
+        for (name, assigns) in self.unresolved {
+            if let Some(&declaration) = self.global_declarations.get(&name.lexeme) {
+                symbols.add_reference(name, declaration, assigns);
+            }
         }
+        symbols
     }
 
     pub fn resolve_statements(&mut self, statements: &[Stmt]) -> Result<()> {
@@ -42,6 +113,84 @@ impl<'a> Resolver<'a> {
         expr.accept(self)
     }
 
+    // Two traits providing a method of the same name is an error unless the class defines
+    // that method itself. Traits which are not known yet are checked when the class is created.
+    fn check_trait_conflicts(&self, name: &Token, traits: &[Expr], methods: &[Stmt]) -> Result<()> {
+        let own_methods: Vec<&str> = methods
+            .iter()
+            .filter_map(|method| match method {
+                Stmt::Function { name, .. } => Some(name.lexeme.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut provided_by: HashMap<&str, &str> = HashMap::new();
+        for used_trait in traits {
+            let trait_name = match used_trait {
+                Expr::Variable { name } => name,
+                _ => unreachable!(),
+            };
+            if trait_name.lexeme == name.lexeme {
+                return Err(Error::ResolveError(
+                    trait_name.clone(),
+                    String::from("A class cannot use itself as a trait."),
+                ));
+            }
+            let method_names = match self.trait_methods(&trait_name.lexeme) {
+                Some(method_names) => method_names,
+                None => continue,
+            };
+            for method_name in method_names {
+                if own_methods.contains(&method_name.as_str()) {
+                    continue;
+                }
+                if let Some(other) = provided_by.insert(method_name, &trait_name.lexeme) {
+                    return Err(Error::ResolveError(
+                        trait_name.clone(),
+                        format!(
+                            "Method '{}' is provided by both '{}' and '{}', override it in the class.",
+                            method_name, other, trait_name.lexeme
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // A `#name` field is only reachable as `this.#name` inside the class that assigns it,
+    // so subclasses, traits and outside code cannot touch it.
+    fn check_private_access(&mut self, object: &Expr, name: &Token, assigns: bool) -> Result<()> {
+        if name.token_type != TokenType::PrivateIdentifier {
+            return Ok(());
+        }
+        if self.current_class == ClassType::None || self.current_class == ClassType::Trait {
+            return Err(Error::ResolveError(
+                name.clone(),
+                format!(
+                    "Private field '{}' can only be used inside a class.",
+                    name.lexeme
+                ),
+            ));
+        }
+        if !matches!(object, Expr::This { .. }) {
+            return Err(Error::ResolveError(
+                name.clone(),
+                format!(
+                    "Private field '{}' can only be accessed through 'this'.",
+                    name.lexeme
+                ),
+            ));
+        }
+        let private_fields = self.private_fields.last_mut().unwrap();
+        if assigns {
+            private_fields.declared.insert(name.lexeme.clone());
+        }
+        private_fields.used.push(name.clone());
+        let key = format!("{}{}", private_fields.class, name.lexeme);
+        self.interpreter.resolve_private(name.clone(), key);
+        Ok(())
+    }
+
     fn resolve_function(
         &mut self,
         _name: &Token,
@@ -54,7 +203,7 @@ impl<'a> Resolver<'a> {
 
         self.begin_scope();
         for param in params {
-            self.declare(param)?;
+            self.declare(param, SymbolKind::Parameter)?;
             self.define(param);
         }
         self.resolve_statements(body)?;
@@ -64,6 +213,7 @@ impl<'a> Resolver<'a> {
     }
 
     fn resolve_local(&mut self, expr: Expr, name: &Token) -> Result<()> {
+        let assigns = matches!(expr, Expr::Assign { .. });
         let scopes_count = self.scopes.len();
         let mut i = scopes_count;
         loop {
@@ -72,6 +222,10 @@ impl<'a> Resolver<'a> {
             }
             if let Some(scope) = self.scopes.get(i - 1) {
                 if let Some(_r) = scope.get(&name.lexeme) {
+                    if let Some(&declaration) = self.declarations[i - 1].get(&name.lexeme) {
+                        self.symbols
+                            .add_reference(name.clone(), declaration, assigns);
+                    }
                     self.interpreter.resolve(expr, scopes_count - 1 - (i - 1))?;
                     return Ok(());
                 }
@@ -79,11 +233,19 @@ impl<'a> Resolver<'a> {
             i -= 1;
         }
         // Not found. Assume it is global.
+        self.unresolved.push((name.clone(), assigns));
         Ok(())
     }
 
-    fn declare(&mut self, name: &Token) -> Result<()> {
+    // Note: `kind` is ignored at the top level, everything declared there is a global
+    fn declare(&mut self, name: &Token, kind: SymbolKind) -> Result<()> {
+        let declaration = self.symbols.declarations.len();
         if self.scopes.is_empty() {
+            self.symbols.declarations.push(name.clone());
+            self.symbols.kinds.push(SymbolKind::Global);
+            self.global_declarations
+                .insert(name.lexeme.clone(), declaration);
+            self.global_traits.remove(&name.lexeme);
             return Ok(());
         }
         let mut scope = self.scopes.pop().unwrap();
@@ -95,6 +257,24 @@ impl<'a> Resolver<'a> {
         }
         scope.insert(name.lexeme.clone(), false);
         self.scopes.push(scope);
+        // Note: `this` and `super` are in the scopes without a declaration, they are never hidden
+        let shadowed = self
+            .scopes
+            .iter()
+            .zip(self.declarations.iter())
+            .rev()
+            .skip(1)
+            .find(|(scope, _)| scope.contains_key(&name.lexeme))
+            .and_then(|(_, declarations)| declarations.get(&name.lexeme));
+        if let Some(&shadowed) = shadowed {
+            self.symbols.shadowed.push((declaration, shadowed));
+        }
+        self.symbols.declarations.push(name.clone());
+        self.symbols.kinds.push(kind);
+        self.declarations
+            .last_mut()
+            .unwrap()
+            .insert(name.lexeme.clone(), declaration);
         Ok(())
     }
 
@@ -109,10 +289,25 @@ impl<'a> Resolver<'a> {
 
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.declarations.push(HashMap::new());
+        self.traits.push(HashMap::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop();
+        self.declarations.pop();
+        self.traits.pop();
+    }
+
+    // Returns the method names of the trait a name refers to, None when it is not a trait
+    // declared in the file.
+    fn trait_methods(&self, name: &str) -> Option<&Vec<String>> {
+        for (scope, traits) in self.scopes.iter().zip(self.traits.iter()).rev() {
+            if scope.contains_key(name) {
+                return traits.get(name);
+            }
+        }
+        self.global_traits.get(name)
     }
 }
 
@@ -146,11 +341,55 @@ impl<'a> ExprVisitor<Result<()>> for Resolver<'a> {
         self.resolve_local(expr, name)?;
         Ok(())
     }
+    fn visit_compound_assign(
+        &mut self,
+        target: &Expr,
+        _operator: &Token,
+        value: &Expr,
+    ) -> Result<()> {
+        self.resolve_expr(value)?;
+        self.resolve_expr(target)
+    }
+    fn visit_increment(&mut self, target: &Expr, _operator: &Token, _postfix: bool) -> Result<()> {
+        self.resolve_expr(target)
+    }
+    fn visit_list(&mut self, elements: &[Expr]) -> Result<()> {
+        for element in elements {
+            self.resolve_expr(element)?;
+        }
+        Ok(())
+    }
+    fn visit_index(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Result<()> {
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)
+    }
+    fn visit_index_set(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<()> {
+        self.resolve_expr(value)?;
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)
+    }
     fn visit_binary(&mut self, left: &Expr, _operator: &Token, right: &Expr) -> Result<()> {
         self.resolve_expr(left)?;
         self.resolve_expr(right)?;
         Ok(())
     }
+    fn visit_conditional(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Result<()> {
+        self.resolve_expr(condition)?;
+        self.resolve_expr(then_branch)?;
+        self.resolve_expr(else_branch)?;
+        Ok(())
+    }
     fn visit_call(&mut self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> Result<()> {
         self.resolve_expr(callee)?;
         for arg in arguments {
@@ -158,7 +397,8 @@ impl<'a> ExprVisitor<Result<()>> for Resolver<'a> {
         }
         Ok(())
     }
-    fn visit_get(&mut self, object: &Expr, _name: &Token) -> Result<()> {
+    fn visit_get(&mut self, object: &Expr, name: &Token) -> Result<()> {
+        self.check_private_access(object, name, false)?;
         self.resolve_expr(object)
     }
     fn visit_grouping(&mut self, expression: &Expr) -> Result<()> {
@@ -173,7 +413,8 @@ impl<'a> ExprVisitor<Result<()>> for Resolver<'a> {
         self.resolve_expr(right)?;
         Ok(())
     }
-    fn visit_set(&mut self, object: &Expr, _name: &Token, value: &Expr) -> Result<()> {
+    fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<()> {
+        self.check_private_access(object, name, true)?;
         self.resolve_expr(value)?;
         self.resolve_expr(object)
     }
@@ -187,6 +428,10 @@ impl<'a> ExprVisitor<Result<()>> for Resolver<'a> {
                 keyword.clone(),
                 "Cannot use 'super' in a class with no superclass.".to_string(),
             )),
+            ClassType::Trait => Err(Error::ResolveError(
+                keyword.clone(),
+                "Cannot use 'super' in a trait.".to_string(),
+            )),
             ClassType::SubClass => {
                 let expr = Expr::Super {
                     keyword: keyword.clone(),
@@ -228,11 +473,16 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
         &mut self,
         name: &Token,
         super_class: &Option<Expr>,
+        traits: &[Expr],
         methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+        setters: &[Stmt],
+        _sealed: bool,
     ) -> Result<()> {
         let enclosing_class = self.current_class;
         self.current_class = ClassType::Class;
-        self.declare(name)?;
+        self.declare(name, SymbolKind::Local)?;
         self.define(name);
 
         if let Some(ext_super_class) = super_class {
@@ -250,6 +500,10 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
             self.current_class = ClassType::SubClass;
             self.resolve_expr(ext_super_class)?
         }
+        self.check_trait_conflicts(name, traits, methods)?;
+        for used_trait in traits {
+            self.resolve_expr(used_trait)?;
+        }
 
         if super_class.is_some() {
             self.begin_scope();
@@ -262,13 +516,24 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
         let mut scope = self.scopes.pop().unwrap();
         scope.insert("this".to_string(), true);
         self.scopes.push(scope);
+        self.private_fields.push(PrivateFields {
+            class: name.lexeme.clone(),
+            ..PrivateFields::default()
+        });
         for method in methods {
             match method {
                 Stmt::Function {
                     name: func_name,
                     params,
                     body,
+                    ..
                 } => {
+                    if func_name.lexeme == "toString" && !params.is_empty() {
+                        return Err(Error::ResolveError(
+                            func_name.clone(),
+                            String::from("toString() must not take parameters."),
+                        ));
+                    }
                     let declaration = if func_name.lexeme == "init" {
                         FunctionType::Initializer
                     } else {
@@ -279,6 +544,69 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
                 _ => unreachable!(),
             }
         }
+        for method in class_methods {
+            match method {
+                Stmt::Function {
+                    name: func_name,
+                    params,
+                    body,
+                    ..
+                } => self.resolve_function(func_name, params, body, FunctionType::Method)?,
+                _ => unreachable!(),
+            }
+        }
+        for getter in getters {
+            match getter {
+                Stmt::Function {
+                    name: func_name,
+                    params,
+                    body,
+                    ..
+                } => {
+                    if !params.is_empty() {
+                        return Err(Error::ResolveError(
+                            func_name.clone(),
+                            String::from("A getter must not take parameters."),
+                        ));
+                    }
+                    self.resolve_function(func_name, params, body, FunctionType::Method)?
+                }
+                _ => unreachable!(),
+            }
+        }
+        for setter in setters {
+            match setter {
+                Stmt::Function {
+                    name: func_name,
+                    params,
+                    body,
+                    ..
+                } => {
+                    if params.len() != 1 {
+                        return Err(Error::ResolveError(
+                            func_name.clone(),
+                            String::from("A setter must take exactly one parameter."),
+                        ));
+                    }
+                    self.resolve_function(func_name, params, body, FunctionType::Method)?
+                }
+                _ => unreachable!(),
+            }
+        }
+        let private_fields = self.private_fields.pop().unwrap();
+        if let Some(undeclared) = private_fields
+            .used
+            .iter()
+            .find(|field| !private_fields.declared.contains(&field.lexeme))
+        {
+            return Err(Error::ResolveError(
+                undeclared.clone(),
+                format!(
+                    "Private field '{}' is never assigned in class '{}'.",
+                    undeclared.lexeme, name.lexeme
+                ),
+            ));
+        }
         self.end_scope();
         if super_class.is_some() {
             self.end_scope();
@@ -287,20 +615,65 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_var_stmt(&mut self, name: &Token, initializer: &Expr) -> Result<()> {
-        self.declare(name)?;
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        _type_annotation: &Option<TypeAnnotation>,
+        initializer: &Expr,
+    ) -> Result<()> {
+        self.declare(name, SymbolKind::Local)?;
         match initializer {
-            Expr::Literal { value } => match value {
-                Literal::None => {}
-                _ => self.resolve_expr(initializer)?,
-            },
+            Expr::Literal {
+                value: Literal::None,
+            } => {}
             _ => self.resolve_expr(initializer)?,
         }
         self.define(name);
         Ok(())
     }
-    fn visit_function_stmt(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> Result<()> {
-        self.declare(name)?;
+    fn visit_trait_stmt(&mut self, name: &Token, methods: &[Stmt]) -> Result<()> {
+        self.declare(name, SymbolKind::Local)?;
+        self.define(name);
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Trait;
+
+        self.begin_scope();
+        let mut scope = self.scopes.pop().unwrap();
+        scope.insert("this".to_string(), true);
+        self.scopes.push(scope);
+        let mut method_names = vec![];
+        for method in methods {
+            match method {
+                Stmt::Function {
+                    name: func_name,
+                    params,
+                    body,
+                    ..
+                } => {
+                    self.resolve_function(func_name, params, body, FunctionType::Method)?;
+                    method_names.push(func_name.lexeme.clone());
+                }
+                _ => unreachable!(),
+            }
+        }
+        self.end_scope();
+
+        self.current_class = enclosing_class;
+        match self.traits.last_mut() {
+            Some(traits) => traits.insert(name.lexeme.clone(), method_names),
+            None => self.global_traits.insert(name.lexeme.clone(), method_names),
+        };
+        Ok(())
+    }
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        _param_types: &[Option<TypeAnnotation>],
+        _return_type: &Option<TypeAnnotation>,
+        body: &[Stmt],
+    ) -> Result<()> {
+        self.declare(name, SymbolKind::Local)?;
         self.define(name);
 
         self.resolve_function(name, params, body, FunctionType::Function)?;
@@ -319,7 +692,7 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
             _ => Ok(()),
         }
     }
-    fn visit_print_stmt(&mut self, expression: &Expr) -> Result<()> {
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) -> Result<()> {
         self.resolve_expr(expression)?;
         Ok(())
     }
@@ -350,4 +723,31 @@ impl<'a> StmtVisitor<Result<()>> for Resolver<'a> {
         self.resolve_statement(body)?;
         Ok(())
     }
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) -> Result<()> {
+        self.resolve_expr(value)
+    }
+    fn visit_try_stmt(
+        &mut self,
+        try_branch: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_branch: &[Stmt],
+        finally_branch: &Option<Vec<Stmt>>,
+    ) -> Result<()> {
+        self.begin_scope();
+        self.resolve_statements(try_branch)?;
+        self.end_scope();
+        if let Some(name) = catch_name {
+            self.begin_scope();
+            self.declare(name, SymbolKind::Catch)?;
+            self.define(name);
+            self.resolve_statements(catch_branch)?;
+            self.end_scope();
+        }
+        if let Some(fb) = finally_branch {
+            self.begin_scope();
+            self.resolve_statements(fb)?;
+            self.end_scope();
+        }
+        Ok(())
+    }
 }
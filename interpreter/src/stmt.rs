@@ -1,10 +1,16 @@
 use super::expr::Expr;
 use super::token::Token;
+use super::types::TypeAnnotation;
 
 pub trait Visitor<T> {
     fn visit_expression_stmt(&mut self, expression: &Expr) -> T;
-    fn visit_print_stmt(&mut self, expression: &Expr) -> T;
-    fn visit_var_stmt(&mut self, name: &Token, initializer: &Expr) -> T;
+    fn visit_print_stmt(&mut self, keyword: &Token, expression: &Expr) -> T;
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        type_annotation: &Option<TypeAnnotation>,
+        initializer: &Expr,
+    ) -> T;
     fn visit_block_stmt(&mut self, statements: &[Stmt]) -> T;
     fn visit_if_stmt(
         &mut self,
@@ -13,10 +19,36 @@ pub trait Visitor<T> {
         else_branch: &Option<Box<Stmt>>,
     ) -> T;
     fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> T;
-    fn visit_function_stmt(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> T;
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        param_types: &[Option<TypeAnnotation>],
+        return_type: &Option<TypeAnnotation>,
+        body: &[Stmt],
+    ) -> T;
     fn visit_return_stmt(&mut self, keyword: &Token, value: &Expr) -> T;
-    fn visit_class_stmt(&mut self, name: &Token, super_class: &Option<Expr>, methods: &[Stmt])
-        -> T;
+    #[allow(clippy::too_many_arguments)]
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        super_class: &Option<Expr>,
+        traits: &[Expr],
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+        setters: &[Stmt],
+        sealed: bool,
+    ) -> T;
+    fn visit_trait_stmt(&mut self, name: &Token, methods: &[Stmt]) -> T;
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> T;
+    fn visit_try_stmt(
+        &mut self,
+        try_branch: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_branch: &[Stmt],
+        finally_branch: &Option<Vec<Stmt>>,
+    ) -> T;
 }
 
 pub trait Acceptor<T> {
@@ -31,9 +63,13 @@ pub enum Stmt {
     Function {
         name: Token,
         params: Vec<Token>,
+        // Note: optional annotations, only read by the type checker
+        param_types: Vec<Option<TypeAnnotation>>,
+        return_type: Option<TypeAnnotation>,
         body: Vec<Stmt>,
     },
     Print {
+        keyword: Token,
         expression: Expr,
     },
     Return {
@@ -42,6 +78,7 @@ pub enum Stmt {
     },
     Var {
         name: Token,
+        type_annotation: Option<TypeAnnotation>,
         initializer: Expr,
     },
     Block {
@@ -51,8 +88,22 @@ pub enum Stmt {
         name: Token,
         // Note: only for Stmt::Variable
         super_class: Option<Expr>,
+        // Note: traits listed after `with`, only for Expr::Variable
+        traits: Vec<Expr>,
         // Note: only for Stmt::Funtion
         methods: Vec<Stmt>,
+        // Note: static methods declared with a leading `class`, only for Stmt::Function
+        class_methods: Vec<Stmt>,
+        // Note: properties computed on access and methods run on assignment, only for Stmt::Function
+        getters: Vec<Stmt>,
+        setters: Vec<Stmt>,
+        // Note: instances of a sealed class reject new fields once `init` has returned
+        sealed: bool,
+    },
+    Trait {
+        name: Token,
+        // Note: only for Stmt::Function
+        methods: Vec<Stmt>,
     },
     If {
         condition: Expr,
@@ -63,14 +114,52 @@ pub enum Stmt {
         condition: Expr,
         body: Box<Stmt>,
     },
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    Try {
+        try_branch: Vec<Stmt>,
+        // Note: catch_branch is empty when there is no catch clause
+        catch_name: Option<Token>,
+        catch_branch: Vec<Stmt>,
+        finally_branch: Option<Vec<Stmt>>,
+    },
+}
+
+impl Stmt {
+    // Note: the line the statement starts on as far as its tokens tell, None for an empty block
+    // or an expression of literals only
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Expression { expression } => expression.line(),
+            Stmt::Var { name, .. }
+            | Stmt::Function { name, .. }
+            | Stmt::Class { name, .. }
+            | Stmt::Trait { name, .. } => Some(name.line),
+            Stmt::Print { keyword, .. }
+            | Stmt::Return { keyword, .. }
+            | Stmt::Throw { keyword, .. } => Some(keyword.line),
+            Stmt::Block { statements } => statements.first().and_then(Stmt::line),
+            Stmt::If { condition, .. } | Stmt::While { condition, .. } => condition.line(),
+            Stmt::Try { try_branch, .. } => try_branch.first().and_then(Stmt::line),
+        }
+    }
 }
 
 impl<T> Acceptor<T> for Stmt {
     fn accept(&self, visitor: &mut dyn Visitor<T>) -> T {
         match self {
-            Stmt::Print { expression } => visitor.visit_print_stmt(expression),
+            Stmt::Print {
+                keyword,
+                expression,
+            } => visitor.visit_print_stmt(keyword, expression),
             Stmt::Expression { expression } => visitor.visit_expression_stmt(expression),
-            Stmt::Var { name, initializer } => visitor.visit_var_stmt(name, &initializer),
+            Stmt::Var {
+                name,
+                type_annotation,
+                initializer,
+            } => visitor.visit_var_stmt(name, type_annotation, initializer),
             Stmt::Block { statements } => visitor.visit_block_stmt(statements),
             Stmt::If {
                 condition,
@@ -78,15 +167,41 @@ impl<T> Acceptor<T> for Stmt {
                 else_branch,
             } => visitor.visit_if_stmt(condition, then_branch, else_branch),
             Stmt::While { condition, body } => visitor.visit_while_stmt(condition, body),
-            Stmt::Function { name, params, body } => {
-                visitor.visit_function_stmt(name, params, body)
-            }
+            Stmt::Function {
+                name,
+                params,
+                param_types,
+                return_type,
+                body,
+            } => visitor.visit_function_stmt(name, params, param_types, return_type, body),
             Stmt::Return { keyword, value } => visitor.visit_return_stmt(keyword, value),
             Stmt::Class {
                 name,
                 super_class,
+                traits,
+                methods,
+                class_methods,
+                getters,
+                setters,
+                sealed,
+            } => visitor.visit_class_stmt(
+                name,
+                super_class,
+                traits,
                 methods,
-            } => visitor.visit_class_stmt(name, super_class, methods),
+                class_methods,
+                getters,
+                setters,
+                *sealed,
+            ),
+            Stmt::Trait { name, methods } => visitor.visit_trait_stmt(name, methods),
+            Stmt::Throw { keyword, value } => visitor.visit_throw_stmt(keyword, value),
+            Stmt::Try {
+                try_branch,
+                catch_name,
+                catch_branch,
+                finally_branch,
+            } => visitor.visit_try_stmt(try_branch, catch_name, catch_branch, finally_branch),
         }
     }
 }
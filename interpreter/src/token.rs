@@ -16,13 +16,16 @@ impl fmt::Display for Literal {
         match self {
             Literal::Isize(i) => write!(f, "{}", i),
             Literal::String(string) => write!(f, "{}", string),
-            Literal::Float(float) => write!(f, "{}", float),
+            // Note: Debug keeps the fraction of integral floats, e.g. `1.0`
+            Literal::Float(float) => write!(f, "{:?}", float),
             Literal::Bool(boolean) => write!(f, "{}", boolean),
             Literal::None => write!(f, "{}", &"nil"),
         }
     }
 }
 
+// Note: this is structural equality so literals can be part of keys of the resolved locals.
+// Floats are compared by bit pattern to agree with Hash. Lox's `==` is Interpreter::is_equal.
 impl Eq for Literal {}
 
 impl PartialEq for Literal {
@@ -31,7 +34,7 @@ impl PartialEq for Literal {
             (Literal::Bool(a), Literal::Bool(b)) => a.eq(b),
             (Literal::Isize(a), Literal::Isize(b)) => a.eq(b),
             (Literal::String(a), Literal::String(b)) => a.eq(b),
-            (Literal::Float(a), Literal::Float(b)) => a.eq(b),
+            (Literal::Float(a), Literal::Float(b)) => a.to_bits() == b.to_bits(),
             (Literal::None, Literal::None) => true,
             (_, _) => false,
         }
@@ -56,15 +59,24 @@ pub struct Token {
     pub lexeme: String,
     pub literal: Literal,
     pub line: usize,
+    // Note: 1-based, tells apart tokens with the same lexeme on one line
+    pub column: usize,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, literal: Literal, line: usize) -> Token {
+    pub fn new(
+        token_type: TokenType,
+        lexeme: String,
+        literal: Literal,
+        line: usize,
+        column: usize,
+    ) -> Token {
         Token {
             token_type,
             lexeme,
             literal,
             line,
+            column,
         }
     }
 }
@@ -7,13 +7,23 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
     Plus,
     Semicolon,
     Slash,
+    TildeSlash,
     Star,
+    Percent,
+    Question,
+    Colon,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character tokens
     Bang,
@@ -24,9 +34,20 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
+    StarStar,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    PlusPlus,
+    MinusMinus,
 
     // Literals
     Identifier,
+    PrivateIdentifier,
     String,
     Number,
 
@@ -47,8 +68,18 @@ pub enum TokenType {
     True,
     Var,
     While,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Is,
+    Trait,
+    With,
 
     // Other
+    // Note: `// ...` including the slashes, only produced for the formatter
+    Comment,
+    #[allow(clippy::upper_case_acronyms)]
     EOF,
 }
 
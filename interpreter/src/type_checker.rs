@@ -0,0 +1,1208 @@
+use super::callable::LoxCallable;
+use super::environment::Environment;
+use super::expr::Expr;
+use super::expr::{Acceptor as ExprAcceptor, Visitor as ExprVisitor};
+use super::interpreter::Interpreter;
+use super::lox_class::LoxClass;
+use super::object::Object;
+use super::stmt::{Acceptor as StmtAcceptor, Stmt, Visitor as StmtVisitor};
+use super::token::Literal;
+use super::token::Token;
+use super::token_type::TokenType;
+use super::types::{Type, TypeAnnotation};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[line {}:{}] Type error: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClassInfo {
+    super_class: Option<String>,
+    // Note: a class with traits may get methods the checker does not know about
+    has_traits: bool,
+    methods: HashMap<String, Type>,
+    class_methods: HashMap<String, Type>,
+    getters: HashMap<String, Type>,
+    // Note: the type of the first assignment to `this.name` in the class body, `init` first
+    fields: HashMap<String, Type>,
+}
+
+// The types of the names declared in one scope.
+#[derive(Debug, Default)]
+struct Scope {
+    types: HashMap<String, Type>,
+    // Note: names declared with an annotation, only their type is enforced on assignment
+    annotated: HashSet<String>,
+    // Note: nullable variables known not to be nil here, after a nil check
+    narrowed: HashMap<String, Type>,
+}
+
+#[derive(Debug)]
+struct FunctionContext {
+    name: String,
+    declared: Option<Type>,
+    returns: Vec<Type>,
+}
+
+// Checks the optional type annotations and infers types for everything else. Values of
+// type `any` are never reported, so unannotated code is only checked where types are obvious
+// from literals and declarations. Expects a program the Resolver has accepted.
+#[derive(Debug)]
+pub struct TypeChecker {
+    scopes: Vec<Scope>,
+    globals: Scope,
+    classes: HashMap<String, ClassInfo>,
+    // Note: the type of `this`, None outside of classes
+    this_types: Vec<Type>,
+    functions: Vec<FunctionContext>,
+    errors: Vec<TypeError>,
+}
+
+impl TypeChecker {
+    pub fn new(globals: &Environment) -> TypeChecker {
+        let globals = globals
+            .values
+            .borrow()
+            .iter()
+            .map(|(name, value)| {
+                let value_type = match value {
+                    Object::Native(function) => Type::Function(
+                        Some(vec![Type::Any; function.arity()]),
+                        Box::new(native_return_type(name)),
+                    ),
+                    Object::Func(function) => {
+                        Type::Function(Some(vec![Type::Any; function.arity()]), Box::new(Type::Any))
+                    }
+                    Object::Class(class) => Type::Class(class.name.clone()),
+                    Object::Literal(Literal::Isize(_)) | Object::Literal(Literal::Float(_)) => {
+                        Type::Number
+                    }
+                    Object::List(_) => Type::List,
+                    _ => Type::Any,
+                };
+                (name.clone(), value_type)
+            })
+            .collect();
+        TypeChecker {
+            scopes: Vec::new(),
+            globals: Scope {
+                types: globals,
+                ..Scope::default()
+            },
+            classes: HashMap::new(),
+            this_types: Vec::new(),
+            functions: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    // Returns the errors sorted by their position in the source.
+    pub fn check(mut self, statements: &[Stmt]) -> Vec<TypeError> {
+        self.declare_globals(statements);
+        self.infer_class_members(statements);
+        for statement in statements {
+            statement.accept(&mut self);
+        }
+        // Note: compound assignments look at their target twice
+        self.errors
+            .sort_by(|a, b| (a.line, a.column, &a.message).cmp(&(b.line, b.column, &b.message)));
+        self.errors.dedup();
+        self.errors
+    }
+
+    fn error(&mut self, token: &Token, message: String) {
+        self.errors.push(TypeError {
+            line: token.line,
+            column: token.column,
+            message,
+        });
+    }
+
+    fn infer(&mut self, expr: &Expr) -> Type {
+        expr.accept(self)
+    }
+
+    // Note: classes and functions may be used before they are declared, so their signatures
+    // are known up front. Their bodies are checked in order.
+    fn declare_globals(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            if let Stmt::Class {
+                name,
+                super_class,
+                traits,
+                ..
+            } = statement
+            {
+                self.classes.insert(
+                    name.lexeme.clone(),
+                    ClassInfo {
+                        super_class: super_class_name(super_class),
+                        has_traits: !traits.is_empty(),
+                        ..ClassInfo::default()
+                    },
+                );
+            }
+        }
+        for statement in statements {
+            match statement {
+                Stmt::Function {
+                    name,
+                    param_types,
+                    return_type,
+                    ..
+                } => {
+                    let signature = self.signature(param_types, return_type);
+                    self.globals.types.insert(name.lexeme.clone(), signature);
+                }
+                Stmt::Class {
+                    name,
+                    methods,
+                    class_methods,
+                    getters,
+                    ..
+                } => {
+                    self.declare_members(name, methods, class_methods, getters);
+                    self.globals
+                        .types
+                        .insert(name.lexeme.clone(), Type::Class(name.lexeme.clone()));
+                }
+                Stmt::Var { name, .. } | Stmt::Trait { name, .. } => {
+                    self.globals.types.insert(name.lexeme.clone(), Type::Any);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Checks the top-level classes once up front, so code before a class knows the types of
+    // its fields and methods. The errors are reported by the real pass.
+    fn infer_class_members(&mut self, statements: &[Stmt]) {
+        let reported = self.errors.len();
+        for statement in statements {
+            if let Stmt::Class { .. } = statement {
+                statement.accept(self);
+            }
+        }
+        self.errors.truncate(reported);
+    }
+
+    fn declare_members(
+        &mut self,
+        name: &Token,
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+    ) {
+        let methods = self.signatures(methods);
+        let class_methods = self.signatures(class_methods);
+        let getters = self
+            .signatures(getters)
+            .into_iter()
+            .map(|(name, getter)| match getter {
+                Type::Function(_, return_type) => (name, *return_type),
+                other => (name, other),
+            })
+            .collect();
+        let info = self.classes.entry(name.lexeme.clone()).or_default();
+        info.methods = methods;
+        info.class_methods = class_methods;
+        info.getters = getters;
+    }
+
+    fn signatures(&mut self, declarations: &[Stmt]) -> HashMap<String, Type> {
+        let mut signatures = HashMap::new();
+        for declaration in declarations {
+            if let Stmt::Function {
+                name,
+                param_types,
+                return_type,
+                ..
+            } = declaration
+            {
+                let signature = self.signature(param_types, return_type);
+                signatures.insert(name.lexeme.clone(), signature);
+            }
+        }
+        signatures
+    }
+
+    fn signature(
+        &mut self,
+        param_types: &[Option<TypeAnnotation>],
+        return_type: &Option<TypeAnnotation>,
+    ) -> Type {
+        let params = param_types
+            .iter()
+            .map(|annotation| self.annotated(annotation))
+            .collect();
+        Type::Function(Some(params), Box::new(self.annotated(return_type)))
+    }
+
+    fn annotated(&mut self, annotation: &Option<TypeAnnotation>) -> Type {
+        let annotation = match annotation {
+            Some(annotation) => annotation,
+            None => return Type::Any,
+        };
+        let name = &annotation.name;
+        let annotated = match name.lexeme.as_str() {
+            "any" => Type::Any,
+            "nil" => Type::Nil,
+            "bool" => Type::Bool,
+            "number" => Type::Number,
+            "string" => Type::String,
+            "list" => Type::List,
+            "fun" => Type::Function(None, Box::new(Type::Any)),
+            class if self.classes.contains_key(class) => Type::Instance(String::from(class)),
+            class if matches!(self.lookup(class), Some(Type::Class(_))) => {
+                Type::Instance(String::from(class))
+            }
+            unknown => {
+                let message = format!("Unknown type '{}'.", unknown);
+                self.error(name, message);
+                Type::Any
+            }
+        };
+        if annotation.nullable {
+            annotated.nullable()
+        } else {
+            annotated
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn current_scope(&mut self) -> &mut Scope {
+        match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => &mut self.globals,
+        }
+    }
+
+    fn define(&mut self, name: &Token, value_type: Type) {
+        let scope = self.current_scope();
+        scope.narrowed.remove(&name.lexeme);
+        scope.annotated.remove(&name.lexeme);
+        scope.types.insert(name.lexeme.clone(), value_type);
+    }
+
+    fn define_annotated(&mut self, name: &Token, declared: Type) {
+        self.define(name, declared);
+        self.current_scope().annotated.insert(name.lexeme.clone());
+    }
+
+    // Note: the type of a read, narrowed by nil checks
+    fn lookup(&self, name: &str) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.globals))
+            .find_map(|scope| scope.narrowed.get(name).or_else(|| scope.types.get(name)))
+            .cloned()
+    }
+
+    fn declaring_scope(&mut self, name: &str) -> Option<&mut Scope> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .chain(std::iter::once(&mut self.globals))
+            .find(|scope| scope.types.contains_key(name))
+    }
+
+    fn narrow(&mut self, name: &Token, narrowed: Type) {
+        self.current_scope()
+            .narrowed
+            .insert(name.lexeme.clone(), narrowed);
+    }
+
+    // Note: an assignment may store nil again, so the variable is no longer narrowed
+    fn forget_narrowing(&mut self, name: &str) {
+        for scope in self
+            .scopes
+            .iter_mut()
+            .rev()
+            .chain(std::iter::once(&mut self.globals))
+        {
+            scope.narrowed.remove(name);
+            if scope.types.contains_key(name) {
+                return;
+            }
+        }
+    }
+
+    // Note: checks a branch of an if, with the variable of a nil check narrowed in it
+    fn check_branch(&mut self, branch: &Stmt, narrowing: Option<(&Token, Type)>) {
+        match narrowing {
+            Some((name, narrowed)) => {
+                self.begin_scope();
+                self.narrow(name, narrowed);
+                branch.accept(self);
+                self.end_scope();
+            }
+            None => branch.accept(self),
+        }
+    }
+
+    fn inherits(&self, class: &str, ancestor: &str) -> bool {
+        let mut current = Some(class);
+        let mut depth = 0;
+        while let Some(name) = current {
+            if name == ancestor {
+                return true;
+            }
+            depth += 1;
+            if depth > self.classes.len() {
+                return false;
+            }
+            current = self
+                .classes
+                .get(name)
+                .and_then(|info| info.super_class.as_deref());
+        }
+        false
+    }
+
+    fn is_assignable(&self, from: &Type, to: &Type) -> bool {
+        match (from, to) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Nil, Type::Nullable(_)) => true,
+            (Type::Nullable(a), Type::Nullable(b)) => self.is_assignable(a, b),
+            (a, Type::Nullable(b)) => self.is_assignable(a, b),
+            (Type::Instance(a), Type::Instance(b)) => self.inherits(a, b),
+            (Type::Function(..), Type::Function(None, _))
+            | (Type::Class(_), Type::Function(None, _)) => true,
+            (Type::Function(Some(a), a_return), Type::Function(Some(b), b_return)) => {
+                a.len() == b.len() && self.is_assignable(a_return, b_return)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    // Note: looks through superclasses, instance members only
+    fn member(&self, class: &str, name: &str) -> Option<Type> {
+        let mut current = Some(class);
+        let mut depth = 0;
+        while let Some(class) = current {
+            let info = self.classes.get(class)?;
+            if let Some(member) = info
+                .fields
+                .get(name)
+                .or_else(|| info.getters.get(name))
+                .or_else(|| info.methods.get(name))
+            {
+                return Some(member.clone());
+            }
+            depth += 1;
+            if depth > self.classes.len() {
+                return None;
+            }
+            current = info.super_class.as_deref();
+        }
+        None
+    }
+
+    // Note: the class whose field `member` finds, None when it finds a getter or method
+    fn field_owner(&self, class: &str, name: &str) -> Option<String> {
+        let mut current = Some(class);
+        let mut depth = 0;
+        while let Some(class) = current {
+            let info = self.classes.get(class)?;
+            if info.fields.contains_key(name) {
+                return Some(String::from(class));
+            }
+            if info.getters.contains_key(name) || info.methods.contains_key(name) {
+                return None;
+            }
+            depth += 1;
+            if depth > self.classes.len() {
+                return None;
+            }
+            current = info.super_class.as_deref();
+        }
+        None
+    }
+
+    fn class_member(&self, class: &str, name: &str) -> Option<Type> {
+        let mut current = Some(class);
+        let mut depth = 0;
+        while let Some(class) = current {
+            let info = self.classes.get(class)?;
+            if let Some(member) = info.class_methods.get(name) {
+                return Some(member.clone());
+            }
+            depth += 1;
+            if depth > self.classes.len() {
+                return None;
+            }
+            current = info.super_class.as_deref();
+        }
+        None
+    }
+
+    // Note: None when traits or classes outside this file could provide `init`
+    fn initializer(&self, class: &str) -> Option<Type> {
+        let mut current = Some(class);
+        let mut depth = 0;
+        while let Some(class) = current {
+            let info = self.classes.get(class)?;
+            if let Some(init) = info.methods.get("init") {
+                return Some(init.clone());
+            }
+            if info.has_traits {
+                return None;
+            }
+            depth += 1;
+            if depth > self.classes.len() {
+                return None;
+            }
+            current = info.super_class.as_deref();
+        }
+        Some(Type::Function(Some(Vec::new()), Box::new(Type::Nil)))
+    }
+
+    fn operator_method(&self, left: &Type, operator: &Token, operands: usize) -> Option<Type> {
+        let class = match left {
+            Type::Instance(class) => class,
+            _ => return None,
+        };
+        let method = LoxClass::operator_method_name(operator.token_type, operands)
+            .and_then(|method| self.member(class, method));
+        match method {
+            Some(Type::Function(_, return_type)) => Some(*return_type),
+            _ => Some(Type::Any),
+        }
+    }
+
+    fn binary_type(&mut self, left: Type, operator: &Token, right: Type) -> Type {
+        if let Some(result) = self.operator_method(&left, operator, 1) {
+            return result;
+        }
+        match operator.token_type {
+            TokenType::EqualEqual | TokenType::BangEqual | TokenType::Is => Type::Bool,
+            TokenType::Plus => match (&left, &right) {
+                (Type::String, _) | (_, Type::String) => Type::String,
+                (Type::Any, _) | (_, Type::Any) => Type::Any,
+                (Type::Number, Type::Number) => Type::Number,
+                _ => self.operand_error(operator, &left, &right, Type::Any),
+            },
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => {
+                if left.is_numeric() && right.is_numeric() {
+                    Type::Bool
+                } else {
+                    self.operand_error(operator, &left, &right, Type::Bool)
+                }
+            }
+            _ => {
+                if left.is_numeric() && right.is_numeric() {
+                    Type::Number
+                } else {
+                    self.operand_error(operator, &left, &right, Type::Number)
+                }
+            }
+        }
+    }
+
+    fn operand_error(&mut self, operator: &Token, left: &Type, right: &Type, result: Type) -> Type {
+        let message = format!(
+            "Operator '{}' cannot be applied to {} and {}.",
+            operator.lexeme, left, right
+        );
+        self.error(operator, message);
+        result
+    }
+
+    fn check_assignment(&mut self, token: &Token, value: &Type, target: &Type, what: &str) {
+        if !self.is_assignable(value, target) {
+            let message = format!("Cannot assign {} to {} of type {}.", value, what, target);
+            self.error(token, message);
+        }
+    }
+
+    fn check_arguments(
+        &mut self,
+        paren: &Token,
+        callee: &str,
+        params: &[Type],
+        arguments: &[Type],
+    ) {
+        if params.len() != arguments.len() {
+            let message = format!(
+                "'{}' expected {} arguments but got {}.",
+                callee,
+                params.len(),
+                arguments.len()
+            );
+            self.error(paren, message);
+            return;
+        }
+        for (index, (param, argument)) in params.iter().zip(arguments).enumerate() {
+            if !self.is_assignable(argument, param) {
+                let message = format!(
+                    "Argument {} of '{}' expects {} but got {}.",
+                    index + 1,
+                    callee,
+                    param,
+                    argument
+                );
+                self.error(paren, message);
+            }
+        }
+    }
+
+    fn check_function(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        signature: &Type,
+        body: &[Stmt],
+        is_initializer: bool,
+    ) -> Type {
+        let (param_types, declared) = match signature {
+            Type::Function(Some(param_types), declared) => (param_types.clone(), *declared.clone()),
+            _ => (vec![Type::Any; params.len()], Type::Any),
+        };
+        let has_declared_return = declared != Type::Any;
+        self.functions.push(FunctionContext {
+            name: name.lexeme.clone(),
+            declared: if has_declared_return {
+                Some(declared.clone())
+            } else {
+                None
+            },
+            returns: Vec::new(),
+        });
+        self.begin_scope();
+        // Note: `any` is the type of unannotated parameters, there is nothing to enforce
+        for (param, param_type) in params.iter().zip(param_types.iter()) {
+            self.define_annotated(param, param_type.clone());
+        }
+        for statement in body {
+            statement.accept(self);
+        }
+        self.end_scope();
+        let context = self.functions.pop().unwrap();
+
+        if has_declared_return
+            && context.returns.is_empty()
+            && !self.is_assignable(&Type::Nil, &declared)
+        {
+            let message = format!(
+                "'{}' is declared to return {} but never returns a value.",
+                name.lexeme, declared
+            );
+            self.error(name, message);
+        }
+        let return_type = if has_declared_return {
+            declared
+        } else if is_initializer {
+            Type::Any
+        } else {
+            context
+                .returns
+                .into_iter()
+                .fold(None, |joined: Option<Type>, returned| match joined {
+                    Some(joined) => Some(joined.join(returned)),
+                    None => Some(returned),
+                })
+                .unwrap_or(Type::Nil)
+        };
+        Type::Function(Some(param_types), Box::new(return_type))
+    }
+
+    // Note: `init` runs first so the fields it sets up give the types for the other methods
+    fn check_methods(&mut self, class: &str, methods: &[Stmt], kind: MethodKind) {
+        let mut ordered: Vec<&Stmt> = methods.iter().collect();
+        ordered.sort_by_key(|method| match method {
+            Stmt::Function { name, .. } => name.lexeme != "init",
+            _ => true,
+        });
+        for method in ordered {
+            if let Stmt::Function {
+                name,
+                params,
+                param_types,
+                return_type,
+                body,
+            } = method
+            {
+                let signature = self.signature(param_types, return_type);
+                let is_initializer = kind == MethodKind::Method && name.lexeme == "init";
+                let checked = self.check_function(name, params, &signature, body, is_initializer);
+                let info = self.classes.entry(String::from(class)).or_default();
+                match kind {
+                    MethodKind::Method => {
+                        info.methods.insert(name.lexeme.clone(), checked);
+                    }
+                    MethodKind::ClassMethod => {
+                        info.class_methods.insert(name.lexeme.clone(), checked);
+                    }
+                    MethodKind::Getter => {
+                        if let Type::Function(_, return_type) = checked {
+                            info.getters.insert(name.lexeme.clone(), *return_type);
+                        }
+                    }
+                    MethodKind::Setter => {}
+                }
+            }
+        }
+    }
+
+    fn assign_field(&mut self, object: &Expr, object_type: &Type, name: &Token, value: &Type) {
+        let class = match object_type {
+            Type::Instance(class) => class.clone(),
+            _ => return,
+        };
+        match self.member(&class, &name.lexeme) {
+            Some(Type::Function(..)) | None if matches!(object, Expr::This { .. }) => {
+                let field_type = match value {
+                    Type::Nil => Type::Any,
+                    other => other.clone(),
+                };
+                let info = self.classes.entry(class).or_default();
+                info.fields.entry(name.lexeme.clone()).or_insert(field_type);
+            }
+            Some(Type::Function(..)) | None => {}
+            Some(member_type) => {
+                if self.is_assignable(value, &member_type) {
+                    return;
+                }
+                match self.field_owner(&class, &name.lexeme) {
+                    // Note: fields have no annotations, so their inferred type widens like a
+                    // variable's
+                    Some(owner) => {
+                        let widened = member_type.join(value.clone());
+                        let info = self.classes.get_mut(&owner).unwrap();
+                        info.fields.insert(name.lexeme.clone(), widened);
+                    }
+                    None => {
+                        let what = format!("field '{}'", name.lexeme);
+                        self.check_assignment(name, value, &member_type, &what);
+                    }
+                }
+            }
+        }
+    }
+
+    fn assign_variable(&mut self, name: &Token, value: &Type) {
+        self.forget_narrowing(&name.lexeme);
+        let (variable_type, annotated) = match self.declaring_scope(&name.lexeme) {
+            Some(scope) => (
+                scope.types[&name.lexeme].clone(),
+                scope.annotated.contains(&name.lexeme),
+            ),
+            None => return,
+        };
+        if annotated {
+            let what = format!("'{}'", name.lexeme);
+            self.check_assignment(name, value, &variable_type, &what);
+        } else if !self.is_assignable(value, &variable_type) {
+            // Note: an inferred type widens to take the new value, e.g. number and string to any
+            let widened = variable_type.join(value.clone());
+            let scope = self.declaring_scope(&name.lexeme).unwrap();
+            scope.types.insert(name.lexeme.clone(), widened);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MethodKind {
+    Method,
+    ClassMethod,
+    Getter,
+    Setter,
+}
+
+fn super_class_name(super_class: &Option<Expr>) -> Option<String> {
+    match super_class {
+        Some(Expr::Variable { name }) => Some(name.lexeme.clone()),
+        _ => None,
+    }
+}
+
+fn native_return_type(name: &str) -> Type {
+    match name {
+        "str" | "readFile" | "formatTime" | "type" => Type::String,
+        "int" | "float" | "sqrt" | "pow" | "abs" | "floor" | "ceil" | "round" | "min" | "max"
+        | "sin" | "cos" | "tan" | "log" | "exp" | "random" | "randomInt" | "clock" | "now" => {
+            Type::Number
+        }
+        "isNaN" | "isInfinite" | "fileExists" | "hasField" => Type::Bool,
+        "readLine" | "getenv" => Type::Nullable(Box::new(Type::String)),
+        "fields" | "methods" | "listDir" => Type::List,
+        _ => Type::Any,
+    }
+}
+
+// Returns the variable a condition compares with nil and whether it checks for `== nil`.
+fn nil_check(condition: &Expr) -> Option<(&Token, bool)> {
+    let (left, operator, right) = match condition {
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => (&**left, operator, &**right),
+        _ => return None,
+    };
+    let is_nil = match operator.token_type {
+        TokenType::EqualEqual => true,
+        TokenType::BangEqual => false,
+        _ => return None,
+    };
+    match (left, right) {
+        (
+            Expr::Variable { name },
+            Expr::Literal {
+                value: Literal::None,
+            },
+        )
+        | (
+            Expr::Literal {
+                value: Literal::None,
+            },
+            Expr::Variable { name },
+        ) => Some((name, is_nil)),
+        _ => None,
+    }
+}
+
+// Note: whether a statement always returns or throws, so the code after it is not reached
+fn exits(statement: &Stmt) -> bool {
+    match statement {
+        Stmt::Return { .. } | Stmt::Throw { .. } => true,
+        Stmt::Block { statements } => statements.iter().any(exits),
+        Stmt::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => exits(then_branch) && exits(else_branch),
+        _ => false,
+    }
+}
+
+fn callee_name(callee: &Expr) -> String {
+    match callee {
+        Expr::Variable { name } => name.lexeme.clone(),
+        Expr::Get { name, .. } => name.lexeme.clone(),
+        Expr::Super { method, .. } => method.lexeme.clone(),
+        _ => String::from("function"),
+    }
+}
+
+impl ExprVisitor<Type> for TypeChecker {
+    fn visit_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Type {
+        let left = self.infer(left);
+        let right = self.infer(right);
+        self.binary_type(left, operator, right)
+    }
+    fn visit_conditional(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Type {
+        self.infer(condition);
+        let then_type = self.infer(then_branch);
+        let else_type = self.infer(else_branch);
+        then_type.join(else_type)
+    }
+    fn visit_grouping(&mut self, expression: &Expr) -> Type {
+        self.infer(expression)
+    }
+    fn visit_literal(&mut self, expr: &Literal) -> Type {
+        match expr {
+            Literal::Isize(_) | Literal::Float(_) => Type::Number,
+            Literal::String(_) => Type::String,
+            Literal::Bool(_) => Type::Bool,
+            Literal::None => Type::Nil,
+        }
+    }
+    fn visit_logical(&mut self, left: &Expr, _operator: &Token, right: &Expr) -> Type {
+        let left = self.infer(left);
+        let right = self.infer(right);
+        left.join(right)
+    }
+    fn visit_unary(&mut self, operator: &Token, right: &Expr) -> Type {
+        let right = self.infer(right);
+        if operator.token_type == TokenType::Bang {
+            return Type::Bool;
+        }
+        if let Some(result) = self.operator_method(&right, operator, 0) {
+            return result;
+        }
+        if !right.is_numeric() {
+            let message = format!(
+                "Operator '{}' cannot be applied to {}.",
+                operator.lexeme, right
+            );
+            self.error(operator, message);
+        }
+        Type::Number
+    }
+    fn visit_variable(&mut self, name: &Token) -> Type {
+        self.lookup(&name.lexeme).unwrap_or(Type::Any)
+    }
+    fn visit_assign(&mut self, name: &Token, value: &Expr) -> Type {
+        let value = self.infer(value);
+        self.assign_variable(name, &value);
+        value
+    }
+    fn visit_compound_assign(&mut self, target: &Expr, operator: &Token, value: &Expr) -> Type {
+        let target_type = self.infer(target);
+        let value = self.infer(value);
+        let operator = Interpreter::arithmetic_operator(operator);
+        let result = self.binary_type(target_type, &operator, value);
+        match target {
+            Expr::Variable { name } => self.assign_variable(name, &result),
+            Expr::Get { object, name } => {
+                let object_type = self.infer(object);
+                self.assign_field(object, &object_type, name, &result);
+            }
+            _ => {}
+        }
+        result
+    }
+    fn visit_increment(&mut self, target: &Expr, operator: &Token, _postfix: bool) -> Type {
+        let target_type = self.infer(target);
+        let arithmetic = Interpreter::arithmetic_operator(operator);
+        if let Some(result) = self.operator_method(&target_type, &arithmetic, 1) {
+            return result;
+        }
+        if !target_type.is_numeric() {
+            let message = format!(
+                "Operator '{}' cannot be applied to {}.",
+                operator.lexeme, target_type
+            );
+            self.error(operator, message);
+        }
+        Type::Number
+    }
+    fn visit_call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Type {
+        let callee_type = self.infer(callee);
+        let arguments: Vec<Type> = arguments
+            .iter()
+            .map(|argument| self.infer(argument))
+            .collect();
+        let name = callee_name(callee);
+        match callee_type {
+            Type::Function(Some(params), return_type) => {
+                self.check_arguments(paren, &name, &params, &arguments);
+                *return_type
+            }
+            Type::Function(None, return_type) => *return_type,
+            Type::Class(class) => {
+                if let Some(Type::Function(Some(params), _)) = self.initializer(&class) {
+                    self.check_arguments(paren, &name, &params, &arguments);
+                }
+                Type::Instance(class)
+            }
+            Type::Any => Type::Any,
+            other => {
+                let message = format!("Cannot call '{}' of type {}.", name, other);
+                self.error(paren, message);
+                Type::Any
+            }
+        }
+    }
+    fn visit_get(&mut self, object: &Expr, name: &Token) -> Type {
+        let object_type = self.infer(object);
+        match &object_type {
+            Type::Instance(class) => self.member(class, &name.lexeme).unwrap_or(Type::Any),
+            Type::Class(class) => self.class_member(class, &name.lexeme).unwrap_or(Type::Any),
+            Type::Any | Type::Nullable(_) | Type::String | Type::List => Type::Any,
+            other => {
+                let message = format!("Cannot read property '{}' of {}.", name.lexeme, other);
+                self.error(name, message);
+                Type::Any
+            }
+        }
+    }
+    fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) -> Type {
+        let value = self.infer(value);
+        let object_type = self.infer(object);
+        match &object_type {
+            Type::Instance(_) => self.assign_field(object, &object_type, name, &value),
+            Type::Any | Type::Nullable(_) | Type::Class(_) => {}
+            other => {
+                let message = format!("Cannot set property '{}' on {}.", name.lexeme, other);
+                self.error(name, message);
+            }
+        }
+        value
+    }
+    fn visit_list(&mut self, elements: &[Expr]) -> Type {
+        for element in elements {
+            self.infer(element);
+        }
+        Type::List
+    }
+    fn visit_index(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Type {
+        let object_type = self.infer(object);
+        let index_type = self.infer(index);
+        if let Some(result) = self.operator_method(&object_type, bracket, 1) {
+            return result;
+        }
+        let result = match object_type {
+            Type::List | Type::Any | Type::Nullable(_) => Type::Any,
+            Type::String => Type::String,
+            other => {
+                let message = format!("Cannot index a value of type {}.", other);
+                self.error(bracket, message);
+                return Type::Any;
+            }
+        };
+        if !index_type.is_numeric() {
+            let message = format!("Index must be a number but got {}.", index_type);
+            self.error(bracket, message);
+        }
+        result
+    }
+    fn visit_index_set(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Type {
+        let object_type = self.infer(object);
+        let index_type = self.infer(index);
+        let value = self.infer(value);
+        match object_type {
+            Type::Instance(_) | Type::Any | Type::Nullable(_) => {}
+            Type::List => {
+                if !index_type.is_numeric() {
+                    let message = format!("Index must be a number but got {}.", index_type);
+                    self.error(bracket, message);
+                }
+            }
+            other => {
+                let message = format!("Cannot assign to an index of {}.", other);
+                self.error(bracket, message);
+            }
+        }
+        value
+    }
+    fn visit_this(&mut self, _keyword: &Token) -> Type {
+        self.this_types.last().cloned().unwrap_or(Type::Any)
+    }
+    fn visit_super(&mut self, _keyword: &Token, method: &Token) -> Type {
+        let super_class = match self.this_types.last() {
+            Some(Type::Instance(class)) => self
+                .classes
+                .get(class)
+                .and_then(|info| info.super_class.clone()),
+            _ => None,
+        };
+        super_class
+            .and_then(|super_class| self.member(&super_class, &method.lexeme))
+            .unwrap_or(Type::Any)
+    }
+}
+
+impl StmtVisitor<()> for TypeChecker {
+    fn visit_expression_stmt(&mut self, expression: &Expr) {
+        self.infer(expression);
+    }
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) {
+        self.infer(expression);
+    }
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        type_annotation: &Option<TypeAnnotation>,
+        initializer: &Expr,
+    ) {
+        let value = self.infer(initializer);
+        match type_annotation {
+            Some(_) => {
+                let declared = self.annotated(type_annotation);
+                let what = format!("'{}'", name.lexeme);
+                self.check_assignment(name, &value, &declared, &what);
+                self.define_annotated(name, declared);
+            }
+            // Note: `var x;` or `var x = nil;` is assigned something else later
+            None if value == Type::Nil => self.define(name, Type::Any),
+            None => self.define(name, value),
+        }
+    }
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) {
+        self.begin_scope();
+        for statement in statements {
+            statement.accept(self);
+        }
+        self.end_scope();
+    }
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) {
+        self.infer(condition);
+        let narrowing =
+            nil_check(condition).and_then(|(name, is_nil)| match self.lookup(&name.lexeme) {
+                Some(Type::Nullable(inner)) => Some((name, is_nil, *inner)),
+                _ => None,
+            });
+        let (name, is_nil, inner) = match narrowing {
+            Some(narrowing) => narrowing,
+            None => {
+                then_branch.accept(self);
+                if let Some(else_branch) = else_branch {
+                    else_branch.accept(self);
+                }
+                return;
+            }
+        };
+        // Note: the branch where the variable is not nil sees its inner type
+        let not_nil = Some((name, inner.clone()));
+        self.check_branch(then_branch, if is_nil { None } else { not_nil.clone() });
+        if let Some(else_branch) = else_branch {
+            self.check_branch(else_branch, if is_nil { not_nil } else { None });
+        }
+        // Note: after `if (x == nil) return;` the rest of the block sees x as not nil
+        let nil_branch = if is_nil {
+            Some(then_branch)
+        } else {
+            else_branch.as_deref()
+        };
+        if nil_branch.is_some_and(exits) {
+            self.narrow(name, inner);
+        }
+    }
+    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) {
+        self.infer(condition);
+        body.accept(self);
+    }
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        param_types: &[Option<TypeAnnotation>],
+        return_type: &Option<TypeAnnotation>,
+        body: &[Stmt],
+    ) {
+        let signature = self.signature(param_types, return_type);
+        self.define(name, signature.clone());
+        let checked = self.check_function(name, params, &signature, body, false);
+        self.define(name, checked);
+    }
+    fn visit_return_stmt(&mut self, keyword: &Token, value: &Expr) {
+        let value = self.infer(value);
+        let declared = match self.functions.last_mut() {
+            Some(context) => {
+                context.returns.push(value.clone());
+                context.declared.clone()
+            }
+            None => return,
+        };
+        if let Some(declared) = declared {
+            if !self.is_assignable(&value, &declared) {
+                let message = format!(
+                    "'{}' returns {} but is declared to return {}.",
+                    self.functions.last().unwrap().name,
+                    value,
+                    declared
+                );
+                self.error(keyword, message);
+            }
+        }
+    }
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        super_class: &Option<Expr>,
+        traits: &[Expr],
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+        setters: &[Stmt],
+        _sealed: bool,
+    ) {
+        // Note: top-level classes were already declared by declare_globals
+        if !self.scopes.is_empty() {
+            self.classes.insert(
+                name.lexeme.clone(),
+                ClassInfo {
+                    super_class: super_class_name(super_class),
+                    has_traits: !traits.is_empty(),
+                    ..ClassInfo::default()
+                },
+            );
+            self.declare_members(name, methods, class_methods, getters);
+        }
+        self.define(name, Type::Class(name.lexeme.clone()));
+
+        self.this_types.push(Type::Instance(name.lexeme.clone()));
+        self.check_methods(&name.lexeme, methods, MethodKind::Method);
+        self.check_methods(&name.lexeme, getters, MethodKind::Getter);
+        self.check_methods(&name.lexeme, setters, MethodKind::Setter);
+        self.this_types.pop();
+        self.this_types.push(Type::Class(name.lexeme.clone()));
+        self.check_methods(&name.lexeme, class_methods, MethodKind::ClassMethod);
+        self.this_types.pop();
+    }
+    fn visit_trait_stmt(&mut self, name: &Token, methods: &[Stmt]) {
+        self.define(name, Type::Any);
+        self.this_types.push(Type::Any);
+        for method in methods {
+            if let Stmt::Function {
+                name,
+                params,
+                param_types,
+                return_type,
+                body,
+            } = method
+            {
+                let signature = self.signature(param_types, return_type);
+                self.check_function(name, params, &signature, body, false);
+            }
+        }
+        self.this_types.pop();
+    }
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) {
+        self.infer(value);
+    }
+    fn visit_try_stmt(
+        &mut self,
+        try_branch: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_branch: &[Stmt],
+        finally_branch: &Option<Vec<Stmt>>,
+    ) {
+        self.visit_block_stmt(try_branch);
+        if let Some(catch_name) = catch_name {
+            self.begin_scope();
+            self.define(catch_name, Type::Any);
+            for statement in catch_branch {
+                statement.accept(self);
+            }
+            self.end_scope();
+        }
+        if let Some(finally_branch) = finally_branch {
+            self.visit_block_stmt(finally_branch);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use super::token::Token;
+use std::fmt;
+
+// Note: written `name` or `name?`, where the name is a builtin type or a class and `?`
+// also allows nil
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeAnnotation {
+    pub name: Token,
+    pub nullable: bool,
+}
+
+impl fmt::Display for TypeAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.nullable {
+            write!(f, "{}?", self.name.lexeme)
+        } else {
+            write!(f, "{}", self.name.lexeme)
+        }
+    }
+}
+
+// Static types used by the checker. `Any` is the type of everything not annotated and not
+// inferred, it is compatible with every other type in both directions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Any,
+    Nil,
+    Bool,
+    Number,
+    String,
+    List,
+    // Note: None for the parameters when only the fact that it is callable is known
+    Function(Option<Vec<Type>>, Box<Type>),
+    // Note: the class object itself, its instances are `Instance`
+    Class(String),
+    Instance(String),
+    Nullable(Box<Type>),
+}
+
+impl Type {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Number | Type::Any)
+    }
+
+    pub fn nullable(self) -> Type {
+        match self {
+            Type::Any | Type::Nil | Type::Nullable(_) => self,
+            other => Type::Nullable(Box::new(other)),
+        }
+    }
+
+    // Note: the type of a value which is either of the two
+    pub fn join(self, other: Type) -> Type {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Type::Any, _) | (_, Type::Any) => Type::Any,
+            (Type::Nil, t) | (t, Type::Nil) => t.nullable(),
+            (Type::Nullable(a), b) | (b, Type::Nullable(a)) if *a == b => Type::Nullable(a),
+            _ => Type::Any,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Any => write!(f, "any"),
+            Type::Nil => write!(f, "nil"),
+            Type::Bool => write!(f, "bool"),
+            Type::Number => write!(f, "number"),
+            Type::String => write!(f, "string"),
+            Type::List => write!(f, "list"),
+            Type::Function(None, _) => write!(f, "fun"),
+            Type::Function(Some(params), return_type) => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "fun({}): {}", params.join(", "), return_type)
+            }
+            Type::Class(name) => write!(f, "class {}", name),
+            Type::Instance(name) => write!(f, "{}", name),
+            Type::Nullable(inner) => write!(f, "{}?", inner),
+        }
+    }
+}